@@ -21,7 +21,7 @@ if [ $? -ne 0 ]; then
 fi
 cd ../..
 
-echo "===== Building Processor Lambda function ====="
+echo "===== Building Processor and Reminder Lambda functions ====="
 cd src/processor
 cargo lambda build --release
 if [ $? -ne 0 ]; then
@@ -52,10 +52,24 @@ if [ $? -ne 0 ]; then
 fi
 cd ../..
 
+echo "===== Deploying Reminder Lambda function ====="
+cd src/processor
+cargo lambda deploy \
+    --iam-role "$ROLE_ARN" \
+    --binary-name "slack-attendance-reminder" \
+    "slack-attendance-reminder"
+if [ $? -ne 0 ]; then
+    echo "Reminder deployment failed!"
+    exit 1
+fi
+cd ../..
+
 echo "===== Deployment successful! ====="
 echo "Receiver function: slack-attendance-receiver"
 echo "Processor function: slack-attendance-processor"
+echo "Reminder function: slack-attendance-reminder"
 echo ""
 echo "Next steps:"
 echo "1. Run terraform apply to create/update infrastructure"
-echo "2. Update environment variables for both Lambda functions"
\ No newline at end of file
+echo "2. Update environment variables for all Lambda functions"
+echo "3. Create an EventBridge schedule that invokes slack-attendance-reminder"
\ No newline at end of file
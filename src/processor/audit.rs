@@ -0,0 +1,152 @@
+use crate::config::Config;
+use crate::events;
+use crate::notion::MAX_TEXT_LENGTH;
+use crate::slack;
+use crate::types::*;
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+
+/// One processed queue message: who sent what, how it was interpreted and what came of it.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<FixedOffset>,
+    pub user_id: String,
+    pub user_name: String,
+    /// SQS message kind (`slash_command`, `block_actions`, ...)
+    pub kind: String,
+    /// Raw command text, button value or triggering message
+    pub input: String,
+    /// How the input was interpreted (parsed command, action id or callback id)
+    pub command: String,
+    pub result: Option<String>,
+    /// Notion pages created or updated while handling the message
+    pub page_ids: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Describes a queued message before it is handled. Read-only traffic that is not a
+    /// command (App Home opens, channel messages matching no punch rule) is not audited.
+    pub fn from_message(config: &Config, message: &SqsMessage, timestamp: DateTime<FixedOffset>) -> Option<Self> {
+        let entry = |kind: &str, user_id: &str, user_name: &str, input: &str, command: String| Self {
+            timestamp,
+            user_id: user_id.to_string(),
+            user_name: user_name.to_string(),
+            kind: kind.to_string(),
+            input: truncate(input),
+            command: truncate(&command),
+            result: None,
+            page_ids: Vec::new(),
+            error: None,
+        };
+
+        match message {
+            SqsMessage::SlashCommand { command, .. } => Some(entry(
+                "slash_command",
+                &command.user_id,
+                &command.user_name,
+                &command.text,
+                match slack::parse_command(&command.text) {
+                    Ok(parsed) => format!("{:?}", parsed),
+                    Err(e) => format!("parse error: {}", e),
+                },
+            )),
+            SqsMessage::BlockActions { command, action_id, .. } => Some(entry(
+                "block_actions",
+                &command.user_id,
+                &command.user_name,
+                &command.text,
+                action_id.clone(),
+            )),
+            SqsMessage::ViewSubmission { user, view, .. } => Some(entry(
+                "view_submission",
+                &user.id,
+                user.display_name(),
+                &view.state.to_string(),
+                view.callback_id.clone(),
+            )),
+            SqsMessage::Shortcut { user, callback_id, .. } => Some(entry(
+                "shortcut",
+                &user.id,
+                user.display_name(),
+                callback_id,
+                callback_id.clone(),
+            )),
+            SqsMessage::FixModal { command, .. } => Some(entry(
+                "fix_modal",
+                &command.user_id,
+                &command.user_name,
+                &command.text,
+                "fix".to_string(),
+            )),
+            SqsMessage::Event { event, .. } => {
+                let (user_id, _, action) = events::match_event(&config.event_rules, event)?;
+                let input = match event {
+                    SlackEvent::Message { text, .. } => text.clone(),
+                    SlackEvent::ReactionAdded { reaction, .. } => format!(":{}:", reaction),
+                    _ => String::new(),
+                };
+                Some(entry("event", &user_id, "", &input, action.label().to_string()))
+            }
+        }
+    }
+
+    /// Adds the outcome once the message has been handled.
+    pub fn finish(mut self, result: Option<String>, error: Option<String>, page_ids: Vec<String>) -> Self {
+        self.result = result.as_deref().map(truncate);
+        self.error = error.as_deref().map(truncate);
+        self.page_ids = page_ids;
+        self
+    }
+}
+
+/// `/attendance audit` の表示。新しい順に `limit` 件まで
+pub fn format_entries(entries: &[AuditEntry], period: &str, limit: usize) -> String {
+    if entries.is_empty() {
+        return format!("{} の監査ログはありません", period);
+    }
+
+    let lines: Vec<String> = entries
+        .iter()
+        .take(limit)
+        .map(|entry| {
+            let outcome = match (&entry.error, &entry.result) {
+                (Some(error), _) => format!("❌ {}", first_line(error)),
+                (None, Some(result)) => first_line(result),
+                (None, None) => "OK".to_string(),
+            };
+            let mut line = format!(
+                "・{} <@{}> [{}] `{}` → {}",
+                entry.timestamp.format("%m/%d %H:%M"),
+                entry.user_id,
+                entry.kind,
+                first_line(&entry.input),
+                outcome
+            );
+            if !entry.page_ids.is_empty() {
+                line.push_str(&format!("（ページ: {}）", entry.page_ids.join(", ")));
+            }
+            line
+        })
+        .collect();
+
+    let mut text = format!("{} の監査ログ（{}件）\n{}", period, entries.len(), lines.join("\n"));
+    if entries.len() > limit {
+        text.push_str(&format!("\nほか{}件", entries.len() - limit));
+    }
+    text
+}
+
+/// 一覧に出す1行目（長い場合は省略する）
+fn first_line(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() > 60 {
+        format!("{}…", line.chars().take(60).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_TEXT_LENGTH).collect()
+}
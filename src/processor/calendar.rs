@@ -0,0 +1,276 @@
+use crate::report::DaySummary;
+use crate::types::*;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 土日・祝日・会社の休日から所定労働日を判定するカレンダー
+#[derive(Debug, Clone, Default)]
+pub struct HolidayCalendar {
+    /// 会社独自の休日（年末年始休暇など）
+    pub company_holidays: BTreeSet<NaiveDate>,
+    /// 土日・祝日でも所定労働日とする日（出勤日の振替など）
+    pub company_workdays: BTreeSet<NaiveDate>,
+}
+
+impl HolidayCalendar {
+    /// `COMPANY_HOLIDAYS` / `COMPANY_WORKDAYS` のカンマ区切りの日付（YYYY-MM-DD）を読み込む
+    pub fn parse(holidays: &str, workdays: &str) -> Result<Self, String> {
+        Ok(Self {
+            company_holidays: parse_dates("COMPANY_HOLIDAYS", holidays)?,
+            company_workdays: parse_dates("COMPANY_WORKDAYS", workdays)?,
+        })
+    }
+
+    /// 所定休日であればその理由（祝日名など）。所定労働日なら None
+    pub fn day_off_reason(&self, date: NaiveDate) -> Option<String> {
+        if self.company_workdays.contains(&date) {
+            return None;
+        }
+        if self.company_holidays.contains(&date) {
+            return Some("会社休日".to_string());
+        }
+        if let Some(name) = national_holidays(date.year()).remove(&date) {
+            return Some(name.to_string());
+        }
+        match date.weekday() {
+            Weekday::Sat => Some("土曜日".to_string()),
+            Weekday::Sun => Some("日曜日".to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn is_working_day(&self, date: NaiveDate) -> bool {
+        self.day_off_reason(date).is_none()
+    }
+
+    /// 月の所定労働日
+    pub fn scheduled_days(&self, year: i32, month: u32) -> Vec<NaiveDate> {
+        let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+            return Vec::new();
+        };
+        first
+            .iter_days()
+            .take_while(|d| d.month() == month)
+            .filter(|d| self.is_working_day(*d))
+            .collect()
+    }
+}
+
+/// 月の所定労働日と実際の出勤の比較
+#[derive(Debug, Clone, Default)]
+pub struct MonthAttendance {
+    /// 所定労働日数
+    pub scheduled_days: usize,
+    /// 打刻のある日数
+    pub worked_days: usize,
+    /// 所定休日に出勤した日と休日の理由
+    pub holiday_work: Vec<(NaiveDate, String)>,
+    /// 打刻も休暇も無い所定労働日（今日より前のみ）
+    pub absences: Vec<NaiveDate>,
+    /// 登録された休暇・欠勤
+    pub leaves: Vec<(NaiveDate, LeaveType)>,
+}
+
+impl MonthAttendance {
+    pub fn format(&self) -> String {
+        let mut text = format!(
+            "所定労働日: {}日  出勤: {}日  休日出勤: {}日  欠勤: {}日",
+            self.scheduled_days,
+            self.worked_days,
+            self.holiday_work.len(),
+            self.absences.len()
+        );
+        if !self.absences.is_empty() {
+            let dates: Vec<String> = self.absences.iter().map(|d| d.format("%m/%d").to_string()).collect();
+            text.push_str(&format!("\n欠勤日: {}", dates.join(", ")));
+        }
+        if !self.leaves.is_empty() {
+            let leaves: Vec<String> = self
+                .leaves
+                .iter()
+                .map(|(date, leave_type)| format!("{} {}", date.format("%m/%d"), leave_type.label()))
+                .collect();
+            text.push_str(&format!(
+                "\n休暇: {}（有給取得 {}日）",
+                leaves.join(", "),
+                self.paid_leave_days()
+            ));
+        }
+        text
+    }
+
+    /// 有給休暇の取得日数（半休は0.5日）
+    pub fn paid_leave_days(&self) -> f64 {
+        self.leaves.iter().filter(|(_, t)| t.is_paid()).map(|(_, t)| t.days()).sum()
+    }
+
+    /// その日に登録された休暇
+    pub fn leave_on(&self, date: NaiveDate) -> Option<LeaveType> {
+        self.leaves.iter().find(|(d, _)| *d == date).map(|(_, t)| *t)
+    }
+}
+
+/// 月の集計結果をカレンダー・休暇と突き合わせる。`today`（YYYY-MM-DD）以降の日は欠勤に数えない
+pub fn month_attendance(
+    calendar: &HolidayCalendar,
+    year: i32,
+    month: u32,
+    days: &[DaySummary],
+    leaves: &[LeaveRecord],
+    today: &str,
+) -> MonthAttendance {
+    let today = NaiveDate::parse_from_str(today, "%Y-%m-%d").unwrap_or(NaiveDate::MAX);
+    let worked: BTreeSet<NaiveDate> = days
+        .iter()
+        .filter(|d| d.in_time.is_some() || d.out_time.is_some())
+        .filter_map(|d| d.naive_date())
+        .collect();
+    let scheduled = calendar.scheduled_days(year, month);
+    let leaves: Vec<(NaiveDate, LeaveType)> = leaves
+        .iter()
+        .filter_map(|l| Some((NaiveDate::parse_from_str(&l.date, "%Y-%m-%d").ok()?, l.leave_type)))
+        .collect();
+    let on_leave = |date: &NaiveDate| leaves.iter().any(|(d, t)| d == date && t.is_full_day_leave());
+
+    MonthAttendance {
+        scheduled_days: scheduled.len(),
+        worked_days: worked.len(),
+        holiday_work: worked
+            .iter()
+            .filter_map(|date| Some((*date, calendar.day_off_reason(*date)?)))
+            .collect(),
+        absences: scheduled
+            .into_iter()
+            .filter(|date| *date < today && !worked.contains(date) && !on_leave(date))
+            .collect(),
+        leaves,
+    }
+}
+
+fn parse_dates(name: &str, text: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("{}: invalid date `{}`", name, s)))
+        .collect()
+}
+
+/// 国民の祝日（現行の祝日法に基づく。春分・秋分は1980〜2099年の近似式）
+pub fn national_holidays(year: i32) -> BTreeMap<NaiveDate, &'static str> {
+    let mut holidays = BTreeMap::new();
+    let mut add = |month: u32, day: u32, name: &'static str| {
+        if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
+            holidays.insert(date, name);
+        }
+    };
+
+    add(1, 1, "元日");
+    add(1, nth_monday(year, 1, 2), "成人の日");
+    add(2, 11, "建国記念の日");
+    add(2, 23, "天皇誕生日");
+    add(3, vernal_equinox_day(year), "春分の日");
+    add(4, 29, "昭和の日");
+    add(5, 3, "憲法記念日");
+    add(5, 4, "みどりの日");
+    add(5, 5, "こどもの日");
+    add(7, nth_monday(year, 7, 3), "海の日");
+    add(8, 11, "山の日");
+    add(9, nth_monday(year, 9, 3), "敬老の日");
+    add(9, autumnal_equinox_day(year), "秋分の日");
+    add(10, nth_monday(year, 10, 2), "スポーツの日");
+    add(11, 3, "文化の日");
+    add(11, 23, "勤労感謝の日");
+
+    // 国民の休日: 祝日に挟まれた平日
+    let sandwiched: Vec<NaiveDate> = holidays
+        .keys()
+        .map(|date| *date + Duration::days(1))
+        .filter(|date| {
+            !holidays.contains_key(date)
+                && date.weekday() != Weekday::Sun
+                && holidays.contains_key(&(*date + Duration::days(1)))
+        })
+        .collect();
+    for date in sandwiched {
+        holidays.insert(date, "国民の休日");
+    }
+
+    // 振替休日: 日曜の祝日の後の最初の平日
+    let sundays: Vec<NaiveDate> = holidays.keys().filter(|d| d.weekday() == Weekday::Sun).copied().collect();
+    for sunday in sundays {
+        let mut date = sunday + Duration::days(1);
+        while holidays.contains_key(&date) {
+            date += Duration::days(1);
+        }
+        holidays.insert(date, "振替休日");
+    }
+
+    holidays
+}
+
+/// 月の第n月曜日の日
+fn nth_monday(year: i32, month: u32, n: u32) -> u32 {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n as u8)
+        .map(|d| d.day())
+        .unwrap_or(0)
+}
+
+fn vernal_equinox_day(year: i32) -> u32 {
+    equinox_day(20.8431, year)
+}
+
+fn autumnal_equinox_day(year: i32) -> u32 {
+    equinox_day(23.2488, year)
+}
+
+fn equinox_day(base: f64, year: i32) -> u32 {
+    let offset = (year - 1980) as f64;
+    (base + 0.242194 * offset - (offset / 4.0).floor()).floor() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn lists_the_2026_holidays() {
+        let holidays = national_holidays(2026);
+        assert_eq!(holidays.len(), 18);
+        assert_eq!(holidays.get(&date("2026-01-12")), Some(&"成人の日"));
+        assert_eq!(holidays.get(&date("2026-03-20")), Some(&"春分の日"));
+        assert_eq!(holidays.get(&date("2026-09-23")), Some(&"秋分の日"));
+        assert_eq!(holidays.get(&date("2026-10-12")), Some(&"スポーツの日"));
+    }
+
+    #[test]
+    fn adds_a_substitute_holiday_after_the_golden_week_run() {
+        // 5/3（日）の振替は 5/4・5/5 の祝日を飛ばして 5/6
+        let holidays = national_holidays(2026);
+        assert_eq!(holidays.get(&date("2026-05-06")), Some(&"振替休日"));
+        // 天皇誕生日 2025-02-23（日）の振替
+        assert_eq!(national_holidays(2025).get(&date("2025-02-24")), Some(&"振替休日"));
+    }
+
+    #[test]
+    fn a_weekday_between_two_holidays_is_a_citizens_holiday() {
+        // 敬老の日 9/21 と秋分の日 9/23 に挟まれた 9/22
+        assert_eq!(national_holidays(2026).get(&date("2026-09-22")), Some(&"国民の休日"));
+    }
+
+    #[test]
+    fn counts_scheduled_days_with_company_days() {
+        let calendar = HolidayCalendar::default();
+        // 2026年9月の平日22日から祝日の3日を除く
+        assert_eq!(calendar.scheduled_days(2026, 9).len(), 19);
+
+        let calendar = HolidayCalendar::parse("2026-09-24", "2026-09-22,2026-09-26").unwrap();
+        assert_eq!(calendar.scheduled_days(2026, 9).len(), 20);
+        assert_eq!(calendar.day_off_reason(date("2026-09-24")), Some("会社休日".to_string()));
+        assert_eq!(calendar.day_off_reason(date("2026-09-21")), Some("敬老の日".to_string()));
+        assert!(calendar.is_working_day(date("2026-09-26")));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::calendar::HolidayCalendar;
+use crate::events::{self, EventRule};
+use crate::labor::LaborRules;
+use crate::notion::{NotionClient, PageLog};
+use crate::policy::{BreakPolicy, RoundingPolicy, WorkPolicy};
+use crate::workspace::{Workspace, Workspaces};
+
+/// Settings read from the processor's environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Channels where punch results are posted for everyone (`in_channel`).
+    /// Everywhere else they are only shown to the user who punched.
+    pub public_punch_channels: Vec<String>,
+    /// Messages and reactions that count as punches (Events API)
+    pub event_rules: Vec<EventRule>,
+    /// JST hour from which users still working are reminded to clock out
+    pub reminder_hour: u32,
+    /// Slack user IDs allowed to run admin-only commands such as team reports
+    pub admin_user_ids: Vec<String>,
+    /// Slack user IDs who approve punch corrections. Falls back to the admins when empty
+    pub correction_approver_ids: Vec<String>,
+    /// Send team reports by DM instead of an ephemeral reply
+    pub team_report_by_dm: bool,
+    /// Company rules applied when computing work time
+    pub work_policy: WorkPolicy,
+    /// Statutory limits used for overtime and 36-agreement checks
+    pub labor_rules: LaborRules,
+    /// Weekends, national holidays and company-specific days off
+    pub calendar: HolidayCalendar,
+    /// Credentials of every Slack workspace this deployment serves
+    pub workspaces: Workspaces,
+    /// Credentials of the workspace the current message came from (see `for_team`)
+    pub workspace: Workspace,
+    /// Notion pages written while handling the current message, for its audit entry
+    pub page_log: PageLog,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let event_rules = match std::env::var("EVENT_RULES") {
+            Ok(json) if !json.trim().is_empty() => events::parse_rules(&json)?,
+            _ => Vec::new(),
+        };
+
+        let rounding = match std::env::var("ROUNDING_POLICY") {
+            Ok(json) if !json.trim().is_empty() => RoundingPolicy::parse(&json)?,
+            _ => RoundingPolicy::default(),
+        };
+
+        let labor_rules = match std::env::var("LABOR_RULES") {
+            Ok(json) if !json.trim().is_empty() => LaborRules::parse(&json)?,
+            _ => LaborRules::default(),
+        };
+
+        let workspaces = Workspaces::from_env()?;
+
+        Ok(Self {
+            workspace: Workspace::default(),
+            workspaces,
+            page_log: PageLog::default(),
+            public_punch_channels: env_list("PUBLIC_PUNCH_CHANNELS"),
+            event_rules,
+            reminder_hour: std::env::var("REMINDER_HOUR")
+                .ok()
+                .and_then(|h| h.parse().ok())
+                .unwrap_or(20),
+            admin_user_ids: env_list("ADMIN_USER_IDS"),
+            correction_approver_ids: env_list("CORRECTION_APPROVER_IDS"),
+            team_report_by_dm: std::env::var("TEAM_REPORT_DELIVERY").as_deref() == Ok("dm"),
+            work_policy: WorkPolicy {
+                breaks: BreakPolicy::parse(&std::env::var("BREAK_DEDUCTION_RULES").unwrap_or_default())?,
+                rounding,
+            },
+            labor_rules,
+            calendar: HolidayCalendar::parse(
+                &std::env::var("COMPANY_HOLIDAYS").unwrap_or_default(),
+                &std::env::var("COMPANY_WORKDAYS").unwrap_or_default(),
+            )?,
+        })
+    }
+
+    /// The same settings with the credentials of the given Slack team, and an empty page log.
+    /// Fails for a team this deployment is not configured to serve.
+    pub fn for_team(&self, team_id: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self {
+            workspace: self.workspaces.for_team(team_id)?,
+            page_log: PageLog::default(),
+            ..self.clone()
+        })
+    }
+
+    /// Client for the workspace's attendance database. Pages it writes go to `page_log`.
+    pub fn notion_client(&self) -> Result<NotionClient, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.workspace.notion_client()?.with_page_log(self.page_log.clone()))
+    }
+
+    pub fn is_admin(&self, user_id: &str) -> bool {
+        self.admin_user_ids.iter().any(|id| id == user_id)
+    }
+
+    /// Users who receive correction requests. With nobody configured, corrections cannot be requested.
+    pub fn approvers(&self) -> &[String] {
+        if self.correction_approver_ids.is_empty() {
+            &self.admin_user_ids
+        } else {
+            &self.correction_approver_ids
+        }
+    }
+
+    pub fn is_approver(&self, user_id: &str) -> bool {
+        self.approvers().iter().any(|id| id == user_id)
+    }
+
+    pub fn punch_response_type(&self, channel_id: &str) -> &'static str {
+        if self.public_punch_channels.iter().any(|c| c == channel_id) {
+            "in_channel"
+        } else {
+            "ephemeral"
+        }
+    }
+}
+
+/// Reads a comma separated environment variable, ignoring blanks.
+fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
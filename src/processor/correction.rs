@@ -0,0 +1,357 @@
+use crate::jst;
+use crate::report::format_time;
+use crate::slack;
+use crate::types::*;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+
+pub const FIX_CALLBACK_ID: &str = "attendance_fix";
+/// 承認者へのDMに付けるボタン。value には申請のページIDを入れる
+pub const APPROVE_ACTION_ID: &str = "correction_approve";
+pub const REJECT_ACTION_ID: &str = "correction_reject";
+
+const TIME_ACTION_ID: &str = "time";
+const REASON_BLOCK_ID: &str = "reason";
+const NEW_IN_BLOCK_ID: &str = "new_in";
+const NEW_OUT_BLOCK_ID: &str = "new_out";
+
+/// A correction requested through the modal.
+#[derive(Debug)]
+pub struct FixSubmission {
+    pub date: String,
+    pub reason: String,
+    /// Changed times and added punches; applied once the request is approved
+    pub detail: CorrectionDetail,
+}
+
+/// `fix` → today, `fix 2026-10-17` → that day.
+pub fn parse_fix_date(text: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    match text.split_whitespace().nth(1) {
+        None => Ok(today),
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("日付の形式が正しくありません: {}。例: fix 2026-10-17", date)),
+    }
+}
+
+pub fn build_fix_modal(date: &str, punches: &[PunchRecord]) -> Result<SlackView, serde_json::Error> {
+    let mut blocks = vec![Block::Section {
+        text: slack::mrkdwn(&format!("*{}* の打刻を修正します", date)),
+    }];
+
+    if punches.is_empty() {
+        blocks.push(Block::Context {
+            elements: vec![slack::mrkdwn("この日の打刻はありません")],
+        });
+    }
+
+    for punch in punches {
+        blocks.push(Block::Input {
+            block_id: punch.page_id.clone(),
+            label: slack::plain_text(punch.action.label()),
+            element: BlockElement::Timepicker {
+                action_id: TIME_ACTION_ID.to_string(),
+                initial_time: Some(punch.timestamp.format("%H:%M").to_string()),
+            },
+            optional: false,
+        });
+    }
+
+    // 出勤・退勤が欠けている日は追加入力欄を出す
+    for (block_id, action) in [
+        (NEW_IN_BLOCK_ID, AttendanceAction::In),
+        (NEW_OUT_BLOCK_ID, AttendanceAction::Out),
+    ] {
+        if !punches.iter().any(|p| p.action == action) {
+            blocks.push(Block::Input {
+                block_id: block_id.to_string(),
+                label: slack::plain_text(&format!("{}（追加）", action.label())),
+                element: BlockElement::Timepicker {
+                    action_id: TIME_ACTION_ID.to_string(),
+                    initial_time: None,
+                },
+                optional: true,
+            });
+        }
+    }
+
+    blocks.push(Block::Input {
+        block_id: REASON_BLOCK_ID.to_string(),
+        label: slack::plain_text("修正理由"),
+        element: BlockElement::PlainTextInput {
+            action_id: REASON_BLOCK_ID.to_string(),
+            multiline: true,
+        },
+        optional: false,
+    });
+
+    let metadata = FixModalMetadata {
+        date: date.to_string(),
+        punches: punches
+            .iter()
+            .map(|p| FixModalPunch {
+                page_id: p.page_id.clone(),
+                action: p.action.clone(),
+                time: p.timestamp.to_rfc3339(),
+            })
+            .collect(),
+    };
+
+    Ok(SlackView::Modal {
+        callback_id: FIX_CALLBACK_ID.to_string(),
+        title: slack::plain_text("打刻修正"),
+        submit: Some(slack::plain_text("修正する")),
+        close: slack::plain_text("キャンセル"),
+        private_metadata: serde_json::to_string(&metadata)?,
+        blocks,
+    })
+}
+
+/// Replaces the loading modal when the correction form cannot be shown.
+pub fn build_error_modal(message: &str) -> SlackView {
+    SlackView::Modal {
+        callback_id: FIX_CALLBACK_ID.to_string(),
+        title: slack::plain_text("打刻修正"),
+        submit: None,
+        close: slack::plain_text("閉じる"),
+        private_metadata: String::new(),
+        blocks: vec![Block::Section {
+            text: slack::mrkdwn(message),
+        }],
+    }
+}
+
+/// Reads the submitted modal and checks the day it would produce. Errors are messages
+/// for the user, so a bad submission never fails the queue message.
+pub fn parse_submission(view: &View, now: DateTime<FixedOffset>) -> Result<FixSubmission, String> {
+    let unreadable = |e: &dyn std::fmt::Display| format!("修正内容を読み取れませんでした（{}）", e);
+    let metadata: FixModalMetadata = serde_json::from_str(&view.private_metadata).map_err(|e| unreadable(&e))?;
+    let values = &view.state["values"];
+    let date = NaiveDate::parse_from_str(&metadata.date, "%Y-%m-%d").map_err(|e| unreadable(&e))?;
+
+    let selected_time = |block_id: &str| -> Result<Option<DateTime<FixedOffset>>, String> {
+        match values[block_id][TIME_ACTION_ID]["selected_time"].as_str() {
+            Some(time) => {
+                let time = NaiveTime::parse_from_str(time, "%H:%M").map_err(|e| unreadable(&e))?;
+                jst::datetime(date, time).map(Some).map_err(|e| unreadable(&e))
+            }
+            None => Ok(None),
+        }
+    };
+
+    let mut detail = CorrectionDetail::default();
+    // 修正後のその日の打刻（検証用）
+    let mut day = Vec::new();
+    for punch in metadata.punches {
+        let original = DateTime::parse_from_rfc3339(&punch.time).map_err(|e| unreadable(&e))?;
+        let corrected = selected_time(&punch.page_id)?.unwrap_or(original);
+        day.push((punch.action.clone(), corrected));
+        if corrected.format("%H:%M").to_string() != original.format("%H:%M").to_string() {
+            detail.changes.push(CorrectionChange {
+                page_id: punch.page_id,
+                action: punch.action,
+                original,
+                corrected,
+            });
+        }
+    }
+
+    for (block_id, action) in [
+        (NEW_IN_BLOCK_ID, AttendanceAction::In),
+        (NEW_OUT_BLOCK_ID, AttendanceAction::Out),
+    ] {
+        if let Some(timestamp) = selected_time(block_id)? {
+            day.push((action.clone(), timestamp));
+            detail.additions.push(CorrectionAddition { action, timestamp });
+        }
+    }
+
+    validate_day(&day, now)?;
+
+    Ok(FixSubmission {
+        date: metadata.date,
+        reason: values[REASON_BLOCK_ID][REASON_BLOCK_ID]["value"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        detail,
+    })
+}
+
+/// 修正後の打刻の並びを確認する: 未来の時刻がないこと、退勤が出勤より後であること、
+/// 休憩の打刻が出勤〜退勤の範囲内にあること
+fn validate_day(day: &[(AttendanceAction, DateTime<FixedOffset>)], now: DateTime<FixedOffset>) -> Result<(), String> {
+    if let Some((action, time)) = day.iter().find(|(_, time)| *time > now) {
+        return Err(format!("{} {} は未来の時刻です", action.label(), format_time(time)));
+    }
+
+    let first_in = day.iter().filter(|(a, _)| *a == AttendanceAction::In).map(|(_, t)| *t).min();
+    let last_out = day.iter().filter(|(a, _)| *a == AttendanceAction::Out).map(|(_, t)| *t).max();
+    if let (Some(in_time), Some(out_time)) = (first_in, last_out) {
+        if out_time <= in_time {
+            return Err(format!(
+                "退勤 {} が出勤 {} より前になっています",
+                format_time(&out_time),
+                format_time(&in_time)
+            ));
+        }
+    }
+
+    for (action, time) in day {
+        if !matches!(action, AttendanceAction::Break | AttendanceAction::Back) {
+            continue;
+        }
+        let before_in = first_in.is_some_and(|in_time| *time < in_time);
+        let after_out = last_out.is_some_and(|out_time| *time > out_time);
+        if before_in || after_out {
+            return Err(format!("{} {} が出勤〜退勤の範囲外です", action.label(), format_time(time)));
+        }
+    }
+
+    Ok(())
+}
+
+/// 承認・却下ボタンの action_id から判定を読み取る
+pub fn parse_decision(action_id: &str) -> Option<ApprovalStatus> {
+    match action_id {
+        APPROVE_ACTION_ID => Some(ApprovalStatus::Approved),
+        REJECT_ACTION_ID => Some(ApprovalStatus::Rejected),
+        _ => None,
+    }
+}
+
+/// 修正内容を1件1行で表す（「・退勤 18:00 → 19:30」「・出勤 09:00 を追加」）
+pub fn describe_detail(detail: &CorrectionDetail) -> Vec<String> {
+    let changes = detail.changes.iter().map(|change| {
+        format!(
+            "・{} {} → {}",
+            change.action.label(),
+            format_time(&change.original),
+            format_time(&change.corrected)
+        )
+    });
+    let additions = detail
+        .additions
+        .iter()
+        .map(|addition| format!("・{} {} を追加", addition.action.label(), format_time(&addition.timestamp)));
+
+    changes.chain(additions).collect()
+}
+
+/// 承認者に送る申請のDM（承認・却下ボタンつき）
+pub fn build_approval_message(request: &CorrectionRequest) -> SlackResponse {
+    let text = format!(
+        "<@{}> さんから {} の打刻修正の申請があります\n{}\n理由: {}",
+        request.user_id,
+        request.date,
+        describe_detail(&request.detail).join("\n"),
+        request.reason
+    );
+
+    SlackResponse {
+        response_type: "ephemeral".to_string(),
+        text: text.clone(),
+        blocks: Some(vec![
+            Block::Section {
+                text: slack::mrkdwn(&text),
+            },
+            Block::Actions {
+                elements: vec![
+                    decision_button("承認", APPROVE_ACTION_ID, &request.page_id, "primary"),
+                    decision_button("却下", REJECT_ACTION_ID, &request.page_id, "danger"),
+                ],
+            },
+        ]),
+    }
+}
+
+fn decision_button(label: &str, action_id: &str, page_id: &str, style: &str) -> BlockElement {
+    BlockElement::Button {
+        text: slack::plain_text(label),
+        action_id: action_id.to_string(),
+        value: page_id.to_string(),
+        style: Some(style.to_string()),
+    }
+}
+
+/// 月次レポートに添える打刻修正の申請の一覧。申請が無ければ None
+pub fn format_requests(requests: &[CorrectionRequest], show_user: bool) -> Option<String> {
+    if requests.is_empty() {
+        return None;
+    }
+
+    let count = |status: ApprovalStatus| requests.iter().filter(|r| r.status == status).count();
+    let lines: Vec<String> = requests
+        .iter()
+        .map(|request| {
+            format!(
+                "・{}{} 【{}】{}（理由: {}）",
+                request.date,
+                if show_user { format!(" {}", request.user_name) } else { String::new() },
+                request.status.label(),
+                describe_detail(&request.detail)
+                    .iter()
+                    .map(|line| line.trim_start_matches('・'))
+                    .collect::<Vec<_>>()
+                    .join("、"),
+                request.reason
+            )
+        })
+        .collect();
+
+    Some(format!(
+        "打刻修正の申請: 承認済み {}件 / 承認待ち {}件 / 却下 {}件\n{}",
+        count(ApprovalStatus::Approved),
+        // 処理中の申請はまだ決まっていないので承認待ちに数える
+        count(ApprovalStatus::Pending) + count(ApprovalStatus::Processing),
+        count(ApprovalStatus::Rejected),
+        lines.join("\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn at(time: &str) -> DateTime<FixedOffset> {
+        testing::at("2026-10-16", time)
+    }
+
+    #[test]
+    fn accepts_an_ordinary_day() {
+        let day = [
+            (AttendanceAction::In, at("09:00")),
+            (AttendanceAction::Break, at("12:00")),
+            (AttendanceAction::Back, at("13:00")),
+            (AttendanceAction::Out, at("18:00")),
+        ];
+        assert_eq!(validate_day(&day, at("23:00")), Ok(()));
+    }
+
+    #[test]
+    fn rejects_clock_out_before_clock_in() {
+        let day = [(AttendanceAction::In, at("09:00")), (AttendanceAction::Out, at("08:30"))];
+        assert_eq!(
+            validate_day(&day, at("23:00")),
+            Err("退勤 08:30 が出勤 09:00 より前になっています".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_breaks_outside_the_working_hours() {
+        let day = [
+            (AttendanceAction::In, at("09:00")),
+            (AttendanceAction::Out, at("18:00")),
+            (AttendanceAction::Break, at("18:30")),
+        ];
+        assert_eq!(
+            validate_day(&day, at("23:00")),
+            Err("休憩入り 18:30 が出勤〜退勤の範囲外です".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_future_times() {
+        let day = [(AttendanceAction::In, at("09:00")), (AttendanceAction::Out, at("18:00"))];
+        assert!(validate_day(&day, at("17:00")).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::types::*;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Maps a message or reaction in a channel to a punch.
+#[derive(Debug, Clone)]
+pub struct EventRule {
+    pub channel: String,
+    pub trigger: EventTrigger,
+    pub action: AttendanceAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum EventTrigger {
+    /// Message text matching this pattern
+    Message(Regex),
+    /// Reaction emoji name without colons (e.g. `wave`)
+    Reaction(String),
+}
+
+/// `EVENT_RULES` の1エントリ。`message` (正規表現) か `reaction` のどちらかを指定する
+/// 例: `[{"channel":"C0123","message":"^おはようございます","action":"in"},{"channel":"C0123","reaction":"wave","action":"out"}]`
+#[derive(Debug, Deserialize)]
+struct RawEventRule {
+    channel: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    reaction: Option<String>,
+    action: AttendanceAction,
+}
+
+pub fn parse_rules(json: &str) -> Result<Vec<EventRule>, Box<dyn std::error::Error + Send + Sync>> {
+    let raw: Vec<RawEventRule> = serde_json::from_str(json)?;
+
+    raw.into_iter()
+        .map(|rule| {
+            let trigger = match (rule.message, rule.reaction) {
+                (Some(pattern), None) => EventTrigger::Message(Regex::new(&pattern)?),
+                (None, Some(reaction)) => EventTrigger::Reaction(reaction.trim_matches(':').to_string()),
+                _ => return Err("EVENT_RULES: specify exactly one of `message` or `reaction`".into()),
+            };
+            Ok(EventRule {
+                channel: rule.channel,
+                trigger,
+                action: rule.action,
+            })
+        })
+        .collect()
+}
+
+/// Returns (user_id, channel_id, action) for the first rule the event matches.
+pub fn match_event(rules: &[EventRule], event: &SlackEvent) -> Option<(String, String, AttendanceAction)> {
+    match event {
+        SlackEvent::Message {
+            channel,
+            user: Some(user),
+            text,
+            subtype: None,
+            bot_id: None,
+        } => rules
+            .iter()
+            .find(|rule| {
+                rule.channel == *channel
+                    && matches!(&rule.trigger, EventTrigger::Message(pattern) if pattern.is_match(text))
+            })
+            .map(|rule| (user.clone(), channel.clone(), rule.action.clone())),
+        SlackEvent::ReactionAdded { user, reaction, item } => rules
+            .iter()
+            .find(|rule| {
+                rule.channel == item.channel
+                    && matches!(&rule.trigger, EventTrigger::Reaction(name) if name == reaction)
+            })
+            .map(|rule| (user.clone(), item.channel.clone(), rule.action.clone())),
+        // 編集・削除・Botの投稿などは打刻に使わない
+        _ => None,
+    }
+}
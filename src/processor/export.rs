@@ -0,0 +1,142 @@
+use crate::policy::WorkPolicy;
+use crate::report::{self, DaySummary};
+use crate::types::*;
+use rust_xlsxwriter::Workbook;
+
+const HEADERS: [&str; 11] = [
+    "日付",
+    "ユーザーID",
+    "ユーザー名",
+    "出勤",
+    "退勤",
+    "勤務場所",
+    "休憩(分)",
+    "休憩控除(分)",
+    "実勤務(分)",
+    "勤務(分)",
+    "メモ",
+];
+
+/// 給与計算向けの1日1行のデータ
+#[derive(Debug, Clone)]
+pub struct ExportRow {
+    pub user_id: String,
+    pub user_name: String,
+    pub day: DaySummary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// 打刻をユーザー・日付順の行に変換する
+pub fn build_rows(punches: &[PunchRecord], policy: &WorkPolicy) -> Vec<ExportRow> {
+    report::group_by_user(punches)
+        .into_iter()
+        .flat_map(|(user_id, user_punches)| {
+            let user_name = user_punches.last().map(|p| p.user_name.clone()).unwrap_or_default();
+            report::summarize_days(&user_punches, policy, None)
+                .into_iter()
+                .map(move |day| ExportRow {
+                    user_id: user_id.clone(),
+                    user_name: user_name.clone(),
+                    day,
+                })
+        })
+        .collect()
+}
+
+pub fn render(rows: &[ExportRow], format: ExportFormat) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    match format {
+        ExportFormat::Csv => to_csv(rows),
+        ExportFormat::Xlsx => to_xlsx(rows),
+    }
+}
+
+fn cells(row: &ExportRow) -> [String; 11] {
+    [
+        row.day.date.clone(),
+        row.user_id.clone(),
+        row.user_name.clone(),
+        row.day.in_time.as_ref().map(report::format_time).unwrap_or_default(),
+        row.day.out_time.as_ref().map(report::format_time).unwrap_or_default(),
+        row.day.location.as_ref().map(|l| l.label()).unwrap_or_default(),
+        row.day.break_minutes().to_string(),
+        row.day.applied_break_minutes().to_string(),
+        // 出勤・退勤が揃っていない日は空欄にする。勤務(分)は端数処理後の値
+        row.day.raw_work_minutes().map(|m| m.to_string()).unwrap_or_default(),
+        row.day.work_minutes().map(|m| m.to_string()).unwrap_or_default(),
+        row.day.notes.join(" / "),
+    ]
+}
+
+fn to_csv(rows: &[ExportRow]) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    // ExcelでUTF-8として開けるようにBOMを付ける
+    let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+    writer.write_record(HEADERS)?;
+    for row in rows {
+        writer.write_record(cells(row).iter().map(|cell| escape_formula(cell)))?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
+/// Excelが数式として評価しないよう、`=` `+` `-` `@` などで始まる文字列に `'` を付ける
+/// （メモやユーザー名は利用者が自由に入力できるため）。数値はそのまま
+fn escape_formula(cell: &str) -> String {
+    let starts_formula = cell.starts_with(['=', '+', '-', '@', '\t', '\r']);
+    if starts_formula && cell.parse::<f64>().is_err() {
+        format!("'{}", cell)
+    } else {
+        cell.to_string()
+    }
+}
+
+fn to_xlsx(rows: &[ExportRow]) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("勤怠")?;
+
+    for (col, header) in HEADERS.iter().enumerate() {
+        worksheet.write_string(0, col as u16, *header)?;
+    }
+
+    for (i, row) in rows.iter().enumerate() {
+        let row_num = i as u32 + 1;
+        for (col, value) in cells(row).into_iter().enumerate() {
+            // 分数の列は数値として書き込み、集計できるようにする
+            match value.parse::<f64>() {
+                Ok(number) if (6..HEADERS.len() - 1).contains(&col) => worksheet.write_number(row_num, col as u16, number)?,
+                _ => worksheet.write_string(row_num, col as u16, value)?,
+            };
+        }
+    }
+
+    Ok(workbook.save_to_buffer()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_cells_that_start_a_formula() {
+        assert_eq!(escape_formula("=HYPERLINK(\"http://example.com\")"), "'=HYPERLINK(\"http://example.com\")");
+        assert_eq!(escape_formula("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_formula("+81"), "+81");
+        assert_eq!(escape_formula("-15"), "-15");
+        assert_eq!(escape_formula("- 早退"), "'- 早退");
+        assert_eq!(escape_formula("早退: 通院"), "早退: 通院");
+    }
+}
@@ -0,0 +1,63 @@
+use crate::report::{self, WorkStatus};
+use crate::slack;
+use crate::types::*;
+
+/// App Home タブに表示するダッシュボードを組み立てる
+pub fn build_home_view(
+    status: &WorkStatus,
+    week_days: &[report::DaySummary],
+    recent_punches: &[PunchRecord],
+    updated_at: &str,
+) -> SlackView {
+    let week_minutes: i64 = week_days.iter().filter_map(|d| d.work_minutes()).sum();
+    let week_work_days = week_days.iter().filter(|d| d.work_minutes().is_some()).count();
+
+    let recent = if recent_punches.is_empty() {
+        "今週の打刻はまだありません".to_string()
+    } else {
+        recent_punches
+            .iter()
+            .map(|p| format!("{}  {}  {}", p.date, report::format_time(&p.timestamp), p.action.label()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let blocks = vec![
+        Block::Header {
+            text: slack::plain_text("勤怠ダッシュボード"),
+        },
+        Block::Section {
+            text: slack::mrkdwn(&format!("*現在の状態*\n{}", status.describe())),
+        },
+        Block::Section {
+            text: slack::mrkdwn(&format!(
+                "*今週の勤務時間*\n{}（{}日）",
+                report::format_duration(week_minutes),
+                week_work_days
+            )),
+        },
+        Block::Actions {
+            elements: quick_actions(status),
+        },
+        Block::Divider,
+        Block::Section {
+            text: slack::mrkdwn(&format!("*最近の打刻*\n{}", recent)),
+        },
+        Block::Context {
+            elements: vec![slack::mrkdwn(&format!("最終更新: {}", updated_at))],
+        },
+    ];
+
+    SlackView::Home { blocks }
+}
+
+/// 今の状態から押せる打刻ボタン
+fn quick_actions(status: &WorkStatus) -> Vec<BlockElement> {
+    match status {
+        WorkStatus::NotStarted | WorkStatus::Finished { .. } => {
+            vec![slack::button("出勤", "in", Some("primary"))]
+        }
+        WorkStatus::Working { .. } => slack::action_buttons(&AttendanceAction::In),
+        WorkStatus::OnBreak { .. } => slack::action_buttons(&AttendanceAction::Break),
+    }
+}
@@ -0,0 +1,71 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::collections::{BTreeMap, HashMap};
+
+/// Bot tokens stored by the receiver's OAuth install flow, one DynamoDB item per team
+/// (`SLACK_INSTALLATIONS_TABLE`). The table is encrypted at rest with a KMS key.
+#[derive(Debug, Clone)]
+pub struct InstallationStore {
+    table_name: String,
+}
+
+impl InstallationStore {
+    /// `None` when the install flow is not deployed.
+    pub fn from_env() -> Option<Self> {
+        let table_name = std::env::var("SLACK_INSTALLATIONS_TABLE").ok().filter(|t| !t.is_empty())?;
+        Some(Self { table_name })
+    }
+
+    async fn client() -> aws_sdk_dynamodb::Client {
+        let config = aws_config::load_from_env().await;
+        aws_sdk_dynamodb::Client::new(&config)
+    }
+
+    /// Bot token of one installed team.
+    pub async fn bot_token(&self, team_id: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let output = Self::client()
+            .await
+            .get_item()
+            .table_name(&self.table_name)
+            .key("team_id", AttributeValue::S(team_id.to_string()))
+            .projection_expression("bot_token")
+            .send()
+            .await?;
+
+        Ok(output.item.as_ref().and_then(bot_token))
+    }
+
+    /// Bot tokens of every installed team, keyed by team ID.
+    pub async fn bot_tokens(&self) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error + Send + Sync>> {
+        let client = Self::client().await;
+        let mut tokens = BTreeMap::new();
+        let mut start_key = None;
+
+        loop {
+            let output = client
+                .scan()
+                .table_name(&self.table_name)
+                .projection_expression("team_id, bot_token")
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await?;
+
+            for item in output.items() {
+                let team_id = item.get("team_id").and_then(|v| v.as_s().ok());
+                if let (Some(team_id), Some(token)) = (team_id, bot_token(item)) {
+                    tokens.insert(team_id.clone(), token);
+                }
+            }
+
+            start_key = output.last_evaluated_key;
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(tokens)
+    }
+}
+
+fn bot_token(item: &HashMap<String, AttributeValue>) -> Option<String> {
+    item.get("bot_token")?.as_s().ok().filter(|token| !token.is_empty()).cloned()
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+
+/// JST = UTC+9。打刻の時刻・日付はすべて日本時間で扱う
+pub fn offset() -> FixedOffset {
+    FixedOffset::east_opt(9 * 3600).unwrap()
+}
+
+/// 現在の日本時間
+pub fn now() -> DateTime<FixedOffset> {
+    Utc::now().with_timezone(&offset())
+}
+
+/// 今日の日付（日本時間）
+pub fn today() -> NaiveDate {
+    now().date_naive()
+}
+
+/// 日本時間の日付と時刻から日時を作る
+pub fn datetime(date: NaiveDate, time: NaiveTime) -> Result<DateTime<FixedOffset>, Box<dyn std::error::Error + Send + Sync>> {
+    date.and_time(time)
+        .and_local_timezone(offset())
+        .single()
+        .ok_or_else(|| "Invalid JST datetime".into())
+}
@@ -0,0 +1,304 @@
+use crate::jst;
+use crate::report::{format_duration, DaySummary};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Weekday};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// 労働基準法・36協定に基づく集計ルール。`LABOR_RULES` で項目ごとに変更できる
+/// （例: `{"legal_holiday":"sat","monthly_overtime_limit_minutes":2700}`）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaborRules {
+    /// 1日の法定労働時間（分）
+    pub daily_limit_minutes: i64,
+    /// 1週の法定労働時間（分）
+    pub weekly_limit_minutes: i64,
+    /// 深夜労働の開始・終了時刻（JST）
+    pub night_start: NaiveTime,
+    pub night_end: NaiveTime,
+    /// 法定休日の曜日
+    pub legal_holiday: Weekday,
+    /// 36協定: 月の時間外労働の上限（分）
+    pub monthly_overtime_limit_minutes: i64,
+    /// 36協定: 月の時間外労働＋休日労働の上限（分、この時間未満であること）
+    pub monthly_overtime_and_holiday_limit_minutes: i64,
+}
+
+impl Default for LaborRules {
+    fn default() -> Self {
+        Self {
+            daily_limit_minutes: 8 * 60,
+            weekly_limit_minutes: 40 * 60,
+            night_start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            night_end: NaiveTime::from_hms_opt(5, 0, 0).unwrap(),
+            legal_holiday: Weekday::Sun,
+            monthly_overtime_limit_minutes: 45 * 60,
+            monthly_overtime_and_holiday_limit_minutes: 100 * 60,
+        }
+    }
+}
+
+impl LaborRules {
+    /// `LABOR_RULES` を読み込む。省略した項目は法定の値
+    pub fn parse(json: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let rules: Self = serde_json::from_str(json).map_err(|e| format!("LABOR_RULES: {}", e))?;
+        if rules.daily_limit_minutes <= 0 || rules.weekly_limit_minutes <= 0 {
+            return Err("LABOR_RULES: daily_limit_minutes and weekly_limit_minutes must be positive".into());
+        }
+        Ok(rules)
+    }
+}
+
+/// 月初を含む週（月曜始まり）のうち前月の日付。週40時間の判定に使う
+pub fn prior_week_dates(year: i32, month: u32) -> Vec<String> {
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return Vec::new();
+    };
+    let monday = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+    monday
+        .iter_days()
+        .take_while(|d| *d < first)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .collect()
+}
+
+/// 期間の労働時間の内訳
+#[derive(Debug, Clone, Default)]
+pub struct LaborSummary {
+    /// 法定休日以外の実労働時間
+    pub total_minutes: i64,
+    /// 法定時間外労働（1日8時間・1週40時間を超えた分）
+    pub statutory_overtime_minutes: i64,
+    /// 深夜労働（22:00〜5:00）。法定休日を含む
+    pub late_night_minutes: i64,
+    /// 法定休日の労働
+    pub holiday_minutes: i64,
+    /// 36協定の上限超過
+    pub breaches: Vec<String>,
+}
+
+impl LaborSummary {
+    pub fn format(&self) -> String {
+        let mut text = format!(
+            "法定時間外: {}  深夜: {}  休日: {}",
+            format_duration(self.statutory_overtime_minutes),
+            format_duration(self.late_night_minutes),
+            format_duration(self.holiday_minutes)
+        );
+        for breach in &self.breaches {
+            text.push_str(&format!("\n⚠️ {}", breach));
+        }
+        text
+    }
+}
+
+/// 勤務区間から時間外・深夜・休日労働を集計する。
+/// 週40時間の判定は月曜始まりの週単位で行う。`prior_days`（期間の最初の週のうち期間より前の日）は
+/// 週の労働時間にだけ数え、内訳には含めない（その日の時間外は前の期間で集計済み）
+pub fn evaluate(rules: &LaborRules, days: &[DaySummary], prior_days: &[DaySummary]) -> LaborSummary {
+    let mut summary = LaborSummary::default();
+    let mut weekly_regular: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+
+    for day in prior_days {
+        let (Some(date), Some(minutes)) = (day.naive_date(), day.work_minutes()) else {
+            continue;
+        };
+        if date.weekday() != rules.legal_holiday {
+            *weekly_regular.entry(week_start(date)).or_insert(0) += minutes.min(rules.daily_limit_minutes);
+        }
+    }
+
+    let mut worked: Vec<(&DaySummary, NaiveDate, i64)> = days
+        .iter()
+        .filter_map(|d| Some((d, d.naive_date()?, d.work_minutes()?)))
+        .collect();
+    worked.sort_by_key(|(_, date, _)| *date);
+
+    for (day, date, minutes) in worked {
+        summary.late_night_minutes += day
+            .work_sessions()
+            .iter()
+            .map(|(start, end)| night_overlap_minutes(rules, start, end))
+            .sum::<i64>();
+
+        // 法定休日の労働は時間外とは別に数える
+        if date.weekday() == rules.legal_holiday {
+            summary.holiday_minutes += minutes;
+            continue;
+        }
+
+        summary.total_minutes += minutes;
+
+        let daily_overtime = (minutes - rules.daily_limit_minutes).max(0);
+        let regular = minutes - daily_overtime;
+
+        // 1日8時間以内の分を週ごとに積み上げ、40時間を超えた分を時間外にする
+        let week_total = weekly_regular.entry(week_start(date)).or_insert(0);
+        let before = *week_total;
+        *week_total += regular;
+        let weekly_overtime = (*week_total - rules.weekly_limit_minutes.max(before)).max(0);
+
+        summary.statutory_overtime_minutes += daily_overtime + weekly_overtime;
+    }
+
+    if summary.statutory_overtime_minutes > rules.monthly_overtime_limit_minutes {
+        summary.breaches.push(format!(
+            "36協定: 時間外労働が月{}時間の上限を超えています",
+            rules.monthly_overtime_limit_minutes / 60
+        ));
+    }
+    if summary.statutory_overtime_minutes + summary.holiday_minutes
+        >= rules.monthly_overtime_and_holiday_limit_minutes
+    {
+        summary.breaches.push(format!(
+            "36協定: 時間外労働と休日労働の合計が月{}時間未満の上限に達しています",
+            rules.monthly_overtime_and_holiday_limit_minutes / 60
+        ));
+    }
+
+    summary
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// 勤務区間のうち深夜帯（JST）に重なる分数
+fn night_overlap_minutes(rules: &LaborRules, start: &DateTime<FixedOffset>, end: &DateTime<FixedOffset>) -> i64 {
+    let start = start.with_timezone(&jst::offset());
+    let end = end.with_timezone(&jst::offset());
+
+    // 前日の22時から始まる深夜帯も含めて、区間にかかる日ごとに重なりを足す
+    let mut minutes = 0;
+    let mut date = start.date_naive() - Duration::days(1);
+    while date <= end.date_naive() {
+        let night_start = date.and_time(rules.night_start);
+        let night_end = (date + Duration::days(1)).and_time(rules.night_end);
+        let overlap_start = start.naive_local().max(night_start);
+        let overlap_end = end.naive_local().min(night_end);
+        if overlap_end > overlap_start {
+            minutes += (overlap_end - overlap_start).num_minutes();
+        }
+        date += Duration::days(1);
+    }
+
+    minutes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{at, punch, summarize};
+    use crate::types::{AttendanceAction, PunchRecord};
+
+    /// (日付, 出勤, 退勤) の一覧から日ごとの集計を作る
+    fn days(shifts: &[(&str, &str, &str)]) -> Vec<DaySummary> {
+        let punches: Vec<PunchRecord> = shifts
+            .iter()
+            .flat_map(|(date, start, end)| {
+                [punch(date, start, AttendanceAction::In), punch(date, end, AttendanceAction::Out)]
+            })
+            .collect();
+        summarize(&punches)
+    }
+
+    #[test]
+    fn counts_hours_beyond_eight_a_day() {
+        let summary = evaluate(&LaborRules::default(), &days(&[("2026-10-05", "09:00", "19:00")]), &[]);
+        assert_eq!(summary.total_minutes, 600);
+        assert_eq!(summary.statutory_overtime_minutes, 120);
+    }
+
+    #[test]
+    fn counts_hours_beyond_forty_a_week_from_the_day_the_week_crosses_it() {
+        // 月〜金の8時間で40時間に達し、土曜の5時間が週の時間外。日曜は法定休日
+        let week = days(&[
+            ("2026-10-05", "09:00", "17:00"),
+            ("2026-10-06", "09:00", "17:00"),
+            ("2026-10-07", "09:00", "17:00"),
+            ("2026-10-08", "09:00", "17:00"),
+            ("2026-10-09", "09:00", "17:00"),
+            ("2026-10-10", "09:00", "14:00"),
+            ("2026-10-11", "09:00", "13:00"),
+        ]);
+        let summary = evaluate(&LaborRules::default(), &week, &[]);
+        assert_eq!(summary.statutory_overtime_minutes, 300);
+        assert_eq!(summary.holiday_minutes, 240);
+        assert_eq!(summary.total_minutes, 45 * 60);
+    }
+
+    #[test]
+    fn daily_overtime_is_not_counted_again_for_the_week() {
+        // 月〜木の10時間はそれぞれ2時間が1日の時間外。週の積み上げは8時間ずつで、金曜で40時間
+        let week = days(&[
+            ("2026-10-05", "08:00", "18:00"),
+            ("2026-10-06", "08:00", "18:00"),
+            ("2026-10-07", "08:00", "18:00"),
+            ("2026-10-08", "08:00", "18:00"),
+            ("2026-10-09", "09:00", "17:00"),
+        ]);
+        let summary = evaluate(&LaborRules::default(), &week, &[]);
+        assert_eq!(summary.statutory_overtime_minutes, 8 * 60);
+    }
+
+    #[test]
+    fn includes_previous_month_days_of_the_first_week() {
+        // 2026-10-01 は木曜。9/28〜9/30 の24時間と合わせて、10/3（土）の8時間が週の時間外
+        let prior = days(&[
+            ("2026-09-28", "09:00", "17:00"),
+            ("2026-09-29", "09:00", "17:00"),
+            ("2026-09-30", "09:00", "17:00"),
+        ]);
+        let october = days(&[
+            ("2026-10-01", "09:00", "17:00"),
+            ("2026-10-02", "09:00", "17:00"),
+            ("2026-10-03", "09:00", "17:00"),
+        ]);
+
+        assert_eq!(prior_week_dates(2026, 10), ["2026-09-28", "2026-09-29", "2026-09-30"]);
+        let summary = evaluate(&LaborRules::default(), &october, &prior);
+        assert_eq!(summary.statutory_overtime_minutes, 8 * 60);
+        assert_eq!(summary.total_minutes, 24 * 60);
+        assert_eq!(evaluate(&LaborRules::default(), &october, &[]).statutory_overtime_minutes, 0);
+    }
+
+    #[test]
+    fn months_starting_on_monday_have_no_prior_week_days() {
+        assert!(prior_week_dates(2026, 6).is_empty());
+    }
+
+    #[test]
+    fn measures_late_night_overlap() {
+        let rules = LaborRules::default();
+
+        assert_eq!(night_overlap_minutes(&rules, &at("2026-10-05", "21:00"), &at("2026-10-05", "23:30")), 90);
+        assert_eq!(night_overlap_minutes(&rules, &at("2026-10-05", "23:00"), &at("2026-10-06", "06:00")), 360);
+        assert_eq!(night_overlap_minutes(&rules, &at("2026-10-06", "03:00"), &at("2026-10-06", "09:00")), 120);
+        assert_eq!(night_overlap_minutes(&rules, &at("2026-10-06", "09:00"), &at("2026-10-06", "18:00")), 0);
+    }
+
+    #[test]
+    fn flags_the_monthly_overtime_limit() {
+        // 平日20日 × 10時間30分 = 時間外50時間
+        let shifts: Vec<String> = (1..=30)
+            .filter_map(|d| NaiveDate::from_ymd_opt(2026, 11, d))
+            .filter(|d| d.weekday().num_days_from_monday() < 5)
+            .take(20)
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .collect();
+        let month: Vec<(&str, &str, &str)> = shifts.iter().map(|d| (d.as_str(), "08:00", "18:30")).collect();
+
+        let summary = evaluate(&LaborRules::default(), &days(&month), &[]);
+        assert_eq!(summary.statutory_overtime_minutes, 50 * 60);
+        assert_eq!(summary.breaches.len(), 1);
+    }
+
+    #[test]
+    fn parses_rule_overrides() {
+        let rules = LaborRules::parse(r#"{"legal_holiday":"sat","night_start":"23:00"}"#).unwrap();
+        assert_eq!(rules.legal_holiday, Weekday::Sat);
+        assert_eq!(rules.night_start, NaiveTime::from_hms_opt(23, 0, 0).unwrap());
+        assert_eq!(rules.weekly_limit_minutes, 40 * 60);
+        assert!(LaborRules::parse(r#"{"weekly_limit":2400}"#).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use crate::types::*;
+use chrono::{Months, NaiveDate};
+
+/// 付与ごとの使用状況
+#[derive(Debug, Clone)]
+pub struct GrantBalance {
+    pub granted_on: NaiveDate,
+    /// この日以降は使えない（付与日から2年）
+    pub expires_on: NaiveDate,
+    pub days: f64,
+    pub used: f64,
+}
+
+impl GrantBalance {
+    pub fn remaining(&self) -> f64 {
+        self.days - self.used
+    }
+}
+
+/// 有給休暇の残日数
+#[derive(Debug, Clone, Default)]
+pub struct LeaveBalance {
+    /// 基準日時点で有効な付与
+    pub grants: Vec<GrantBalance>,
+    /// 付与が足りず、どの付与からも差し引けなかった日数
+    pub overdrawn: f64,
+}
+
+impl LeaveBalance {
+    pub fn remaining(&self) -> f64 {
+        self.grants.iter().map(|g| g.remaining()).sum()
+    }
+
+    pub fn format(&self) -> String {
+        let mut text = format!("有給休暇の残日数: {}日", self.remaining());
+
+        if self.grants.is_empty() {
+            text.push_str("\n有効な付与がありません");
+        }
+        for grant in &self.grants {
+            text.push_str(&format!(
+                "\n・{} 付与 {}日（使用 {}日 / 残 {}日、{} まで有効）",
+                grant.granted_on,
+                grant.days,
+                grant.used,
+                grant.remaining(),
+                grant.expires_on.pred_opt().unwrap_or(grant.expires_on)
+            ));
+        }
+        if self.overdrawn > 0.0 {
+            text.push_str(&format!("\n⚠️ 付与日数を超えて取得した有給休暇: {}日", self.overdrawn));
+        }
+
+        text
+    }
+}
+
+/// 付与と取得済みの有給休暇から `today` 時点の残日数を計算する。
+/// 取得した有給休暇は、その日に有効な付与のうち古いものから差し引く
+pub fn compute_balance(grants: &[LeaveGrant], leaves: &[LeaveRecord], today: NaiveDate) -> LeaveBalance {
+    let mut balances: Vec<GrantBalance> = grants
+        .iter()
+        .filter_map(|grant| {
+            let granted_on = NaiveDate::parse_from_str(&grant.date, "%Y-%m-%d").ok()?;
+            Some(GrantBalance {
+                granted_on,
+                expires_on: granted_on.checked_add_months(Months::new(24))?,
+                days: grant.days,
+                used: 0.0,
+            })
+        })
+        .collect();
+    balances.sort_by_key(|g| g.granted_on);
+
+    let mut used: Vec<(NaiveDate, f64)> = leaves
+        .iter()
+        .filter(|l| l.leave_type.is_paid())
+        .filter_map(|l| Some((NaiveDate::parse_from_str(&l.date, "%Y-%m-%d").ok()?, l.leave_type.days())))
+        .collect();
+    used.sort_by_key(|(date, _)| *date);
+
+    let mut overdrawn = 0.0;
+    for (date, mut days) in used {
+        for grant in balances
+            .iter_mut()
+            .filter(|g| g.granted_on <= date && date < g.expires_on)
+        {
+            let take = days.min(grant.remaining());
+            grant.used += take;
+            days -= take;
+            if days <= 0.0 {
+                break;
+            }
+        }
+        overdrawn += days;
+    }
+
+    LeaveBalance {
+        grants: balances
+            .into_iter()
+            .filter(|g| g.granted_on <= today && today < g.expires_on)
+            .collect(),
+        overdrawn,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(date: &str, days: f64) -> LeaveGrant {
+        LeaveGrant {
+            user_id: "U1".to_string(),
+            user_name: "user".to_string(),
+            date: date.to_string(),
+            days,
+        }
+    }
+
+    fn leave(date: &str, leave_type: LeaveType) -> LeaveRecord {
+        LeaveRecord {
+            page_id: String::new(),
+            user_id: "U1".to_string(),
+            user_name: "user".to_string(),
+            date: date.to_string(),
+            leave_type,
+        }
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn uses_the_oldest_grant_first() {
+        let grants = [grant("2025-04-01", 10.0), grant("2024-04-01", 2.0)];
+        let leaves = [
+            leave("2025-05-01", LeaveType::Paid),
+            leave("2025-05-02", LeaveType::Paid),
+            leave("2025-05-07", LeaveType::HalfAm),
+            leave("2025-05-08", LeaveType::Sick),
+        ];
+
+        let balance = compute_balance(&grants, &leaves, date("2025-06-01"));
+        assert_eq!(balance.grants.len(), 2);
+        assert_eq!(balance.grants[0].granted_on, date("2024-04-01"));
+        assert_eq!(balance.grants[0].used, 2.0);
+        assert_eq!(balance.grants[1].used, 0.5);
+        assert_eq!(balance.remaining(), 9.5);
+        assert_eq!(balance.overdrawn, 0.0);
+    }
+
+    #[test]
+    fn grants_expire_two_years_after_they_are_given() {
+        let grants = [grant("2024-04-01", 10.0), grant("2025-04-01", 11.0)];
+        let leaves = [leave("2024-05-01", LeaveType::Paid)];
+
+        let before = compute_balance(&grants, &leaves, date("2026-03-31"));
+        assert_eq!(before.remaining(), 20.0);
+
+        let after = compute_balance(&grants, &leaves, date("2026-04-01"));
+        assert_eq!(after.grants.len(), 1);
+        assert_eq!(after.remaining(), 11.0);
+    }
+
+    #[test]
+    fn leave_taken_without_a_valid_grant_is_overdrawn() {
+        let grants = [grant("2025-04-01", 1.0)];
+        let leaves = [
+            // 付与前の取得
+            leave("2025-03-31", LeaveType::Paid),
+            leave("2025-04-10", LeaveType::Paid),
+            leave("2025-04-11", LeaveType::HalfPm),
+        ];
+
+        let balance = compute_balance(&grants, &leaves, date("2025-05-01"));
+        assert_eq!(balance.remaining(), 0.0);
+        assert_eq!(balance.overdrawn, 1.5);
+    }
+
+    #[test]
+    fn only_the_two_half_days_can_share_a_date() {
+        assert!(!LeaveType::HalfAm.conflicts_with(&LeaveType::HalfPm));
+        assert!(!LeaveType::HalfPm.conflicts_with(&LeaveType::HalfAm));
+        assert!(LeaveType::HalfAm.conflicts_with(&LeaveType::HalfAm));
+        assert!(LeaveType::HalfPm.conflicts_with(&LeaveType::Paid));
+        assert!(LeaveType::Sick.conflicts_with(&LeaveType::HalfAm));
+    }
+}
@@ -0,0 +1,23 @@
+//! Modules shared by the SQS processor and the scheduled reminder Lambda.
+
+pub mod audit;
+pub mod calendar;
+pub mod config;
+pub mod correction;
+pub mod events;
+pub mod export;
+pub mod home;
+pub mod installation;
+pub mod jst;
+pub mod labor;
+pub mod leave;
+pub mod notion;
+pub mod policy;
+pub mod report;
+pub mod slack;
+pub mod task;
+pub mod types;
+pub mod workspace;
+
+#[cfg(test)]
+mod testing;
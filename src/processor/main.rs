@@ -1,83 +1,530 @@
-mod notion;
-mod slack;
-mod types;
+use slack_attendance_processor::{
+    audit, calendar, config, correction, events, export, home, jst, labor, leave, notion, report, slack, task, types,
+};
 
 use aws_lambda_events::event::sqs::SqsEvent;
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
-use chrono::{Utc, Local, Datelike, FixedOffset};
-use serde::{Deserialize, Serialize};
+use chrono::{Datelike, Days, NaiveDate, NaiveTime};
+use config::Config;
 use types::*;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct SqsMessage {
-    command: SlackCommand,
-    timestamp: String,
-}
-
 async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<(), Error> {
+    let mut config = Config::from_env()?;
+
     // Process each SQS message
     for record in event.payload.records {
         if let Some(body) = record.body {
             // Parse the SQS message
             let sqs_message: SqsMessage = serde_json::from_str(&body)?;
-            let command = sqs_message.command;
-            
-            // Process the command
-            let response_text = if command.text.trim() == "report" {
-                handle_report(&command).await?
-            } else {
-                handle_attendance(&command).await?
+
+            // 送信元のワークスペースの Notion・Slack を使う（アプリをインストールしたワークスペースはそのトークン）
+            config.workspaces.load_installation(sqs_message.team_id()).await?;
+            let config = match config.for_team(sqs_message.team_id()) {
+                Ok(config) => config,
+                Err(e) => {
+                    // 再試行しても処理できないので捨てる。受付Lambdaは TEAM_CONFIGS も DEFAULT_TEAM_ID も
+                    // 無いときアプリをインストールしただけのワークスペースを通すので、ここで断る
+                    tracing::error!("Dropping message: {}", e);
+                    reply_not_configured(&sqs_message).await;
+                    continue;
+                }
             };
-            
-            // Send delayed response to Slack
-            send_delayed_response(&command.response_url, &response_text).await?;
+
+            let entry = audit::AuditEntry::from_message(&config, &sqs_message, jst::now());
+
+            let result = process_message(&config, sqs_message).await;
+
+            if let Some(entry) = entry {
+                let (summary, error) = match &result {
+                    Ok(summary) => (summary.clone(), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                write_audit(&config, &entry.finish(summary, error, config.page_log.take())).await;
+            }
+            result?;
         }
     }
     
     Ok(())
 }
 
-async fn send_delayed_response(response_url: &str, text: &str) -> Result<(), Error> {
-    let client = reqwest::Client::new();
-    
-    let response = SlackResponse {
-        response_type: "in_channel".to_string(),
+/// Tells the user that the workspace cannot be served yet, when the message came with a
+/// response_url. Events and the other interactions are dropped silently.
+async fn reply_not_configured(sqs_message: &SqsMessage) {
+    let command = match sqs_message {
+        SqsMessage::SlashCommand { command, .. }
+        | SqsMessage::BlockActions { command, .. }
+        | SqsMessage::FixModal { command, .. } => command,
+        _ => return,
+    };
+    if command.response_url.is_empty() {
+        return;
+    }
+
+    let response = ephemeral_response(
+        "このワークスペースでは勤怠管理の設定がまだ完了していません。管理者に連絡してください。".to_string(),
+    );
+    if let Err(e) = send_delayed_response(&command.response_url, &response).await {
+        tracing::error!("Failed to reply to unconfigured workspace: {}", e);
+    }
+}
+
+/// Handles one queued message. Returns the reply text for slash commands, for the audit log.
+async fn process_message(config: &Config, sqs_message: SqsMessage) -> Result<Option<String>, Error> {
+    match sqs_message {
+        SqsMessage::SlashCommand { command, .. } => {
+            let response = handle_command(config, &command).await?;
+            deliver(config, &command, &response).await?;
+            return Ok(Some(response.text));
+        }
+        SqsMessage::BlockActions { command, action_id, .. } => {
+            handle_block_action(config, &command, &action_id).await?;
+        }
+        SqsMessage::ViewSubmission { user, view, .. } => {
+            handle_view_submission(config, &user, &view).await?;
+        }
+        SqsMessage::Shortcut { user, team, callback_id, trigger_id, .. } => {
+            handle_shortcut(config, &user, &team, &callback_id, &trigger_id).await?;
+        }
+        SqsMessage::FixModal { command, view_id, .. } => {
+            handle_fix_modal(config, &command, &view_id).await?;
+        }
+        SqsMessage::Event { event, .. } => {
+            handle_event(config, &event).await?;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Appends the entry to the audit log: always as a JSON log line, and to the workspace's
+/// audit database when one is configured. Failures here never fail the message.
+async fn write_audit(config: &Config, entry: &audit::AuditEntry) {
+    match serde_json::to_string(entry) {
+        Ok(json) => tracing::info!(target: "audit", "{}", json),
+        Err(e) => tracing::warn!("Failed to serialize audit entry: {}", e),
+    }
+
+    let Some(audit_client) = config.workspace.audit_client() else {
+        return;
+    };
+    if let Err(e) = audit_client.create_audit_entry(entry).await {
+        tracing::warn!("Failed to write audit entry: {}", e);
+    }
+}
+
+async fn handle_command(config: &Config, command: &SlackCommand) -> Result<SlackResponse, Error> {
+    match slack::parse_command(&command.text) {
+        Ok(slack::Command::Punch(punch)) => {
+            record_punch(config, &command.user_id, &command.user_name, &command.channel_id, punch).await
+        }
+        Ok(slack::Command::Report { scope: slack::ReportScope::Own, month }) => {
+            handle_report(config, command, month).await
+        }
+        Ok(slack::Command::Report { scope: slack::ReportScope::Team, month }) => {
+            handle_team_report(config, command, month).await
+        }
+        Ok(slack::Command::Status) => handle_status(config, command).await,
+        Ok(slack::Command::Export {
+            scope,
+            user_id,
+            month,
+            format,
+        }) => handle_export(config, command, scope, user_id, month, format).await,
+        Ok(slack::Command::Leave { date, leave_type }) => handle_leave(config, command, date, leave_type).await,
+        Ok(slack::Command::LeaveBalance) => handle_leave_balance(config, command).await,
+        Ok(slack::Command::Task { project }) => handle_task(config, command, project).await,
+        Ok(slack::Command::LeaveGrant { user_id, date, days }) => {
+            handle_leave_grant(config, command, &user_id, date, days).await
+        }
+        Ok(slack::Command::AdminPunch { user_id, action, time, date, reason }) => {
+            handle_admin_punch(config, command, &user_id, action, time, date, reason).await
+        }
+        Ok(slack::Command::Audit { user_id, period }) => handle_audit(config, command, user_id, period).await,
+        Err(e) => Ok(ephemeral_response(e)),
+    }
+}
+
+/// Replies through `response_url` when Slack gave us one, otherwise by DM
+/// (shortcuts and App Home buttons have no response_url).
+async fn deliver(config: &Config, command: &SlackCommand, response: &SlackResponse) -> Result<(), Error> {
+    if command.response_url.is_empty() {
+        let slack_client = config.workspace.slack_client()?;
+        slack_client.post_message(&command.user_id, response).await?;
+    } else {
+        send_delayed_response(&command.response_url, response).await?;
+    }
+
+    Ok(())
+}
+
+async fn handle_block_action(config: &Config, command: &SlackCommand, action_id: &str) -> Result<(), Error> {
+    // 打刻ボタンはスラッシュコマンドと同じ処理に流す
+    if action_id.starts_with("attendance_") {
+        let response = handle_command(config, command).await?;
+        deliver(config, command, &response).await?;
+    } else if action_id == slack::REMINDER_OUT_ACTION_ID {
+        let response = handle_reminder_out(config, command).await?;
+        deliver(config, command, &response).await?;
+    } else if let Some(status) = correction::parse_decision(action_id) {
+        handle_correction_decision(config, command, status).await?;
+    } else {
+        tracing::warn!("Unhandled block action: {}", action_id);
+    }
+
+    Ok(())
+}
+
+/// Clock-out button on a reminder DM. `command.text` holds the date the reminder was sent for,
+/// so a click after midnight still closes that day's session instead of starting a new day.
+async fn handle_reminder_out(config: &Config, command: &SlackCommand) -> Result<SlackResponse, Error> {
+    let date = NaiveDate::parse_from_str(&command.text, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid reminder date `{}`: {}", command.text, e))?;
+
+    // 日付をまたいだ退勤として扱うのは翌日まで。それより古いリマインドは打刻修正で直してもらう
+    if date < jst::today() - Days::new(1) {
+        return Ok(ephemeral_response(format!(
+            "{} の退勤はこのボタンでは記録できません。`/attendance fix {}` で打刻修正を申請してください",
+            date, date
+        )));
+    }
+
+    let punch = slack::PunchRequest {
+        work_date: Some(date),
+        ..AttendanceAction::Out.into()
+    };
+    record_punch(config, &command.user_id, &command.user_name, &command.channel_id, punch).await
+}
+
+async fn handle_view_submission(config: &Config, user: &InteractionUser, view: &View) -> Result<(), Error> {
+    match view.callback_id.as_str() {
+        correction::FIX_CALLBACK_ID => handle_fix_submission(config, user, view).await,
+        other => {
+            tracing::warn!("Unhandled view submission: {}", other);
+            Ok(())
+        }
+    }
+}
+
+async fn handle_fix_modal(config: &Config, command: &SlackCommand, view_id: &str) -> Result<(), Error> {
+    let slack_client = config.workspace.slack_client()?;
+
+    let today = jst::today();
+    let date = match correction::parse_fix_date(&command.text, today) {
+        Ok(d) => d.format("%Y-%m-%d").to_string(),
+        Err(e) => {
+            slack_client
+                .update_view(view_id, &correction::build_error_modal(&e))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let notion_client = config.notion_client()?;
+    let punches = notion_client.get_daily_records(&command.user_id, &date).await?;
+
+    // 受付Lambdaが開いた「読み込み中」モーダルを打刻入力フォームに差し替える
+    let view = correction::build_fix_modal(&date, &punches)?;
+    slack_client.update_view(view_id, &view).await?;
+
+    Ok(())
+}
+
+/// Stores the correction as a pending request and asks the approvers by DM. Every
+/// correction needs sign-off from someone other than the requester, so it is refused
+/// when no such approver is configured.
+async fn handle_fix_submission(config: &Config, user: &InteractionUser, view: &View) -> Result<(), Error> {
+    let slack_client = config.workspace.slack_client()?;
+    let submission = match correction::parse_submission(view, jst::now()) {
+        Ok(submission) => submission,
+        Err(e) => {
+            let text = format!("打刻修正を受け付けられませんでした: {}\n`/attendance fix` からもう一度入力してください", e);
+            slack_client.post_message(&user.id, &ephemeral_response(text)).await?;
+            return Ok(());
+        }
+    };
+
+    if submission.detail.is_empty() {
+        let text = format!("{} の打刻に変更はありませんでした", submission.date);
+        slack_client.post_message(&user.id, &ephemeral_response(text)).await?;
+        return Ok(());
+    }
+
+    // 申請者本人は自分の申請を承認できない
+    let approvers: Vec<&String> = config.approvers().iter().filter(|id| **id != user.id).collect();
+    if approvers.is_empty() {
+        let text = format!(
+            "{} の打刻修正を申請できませんでした。承認者（CORRECTION_APPROVER_IDS / ADMIN_USER_IDS）に申請者以外のユーザーが設定されていません。管理者に連絡してください",
+            submission.date
+        );
+        slack_client.post_message(&user.id, &ephemeral_response(text)).await?;
+        return Ok(());
+    }
+
+    let notion_client = config.notion_client()?;
+
+    let mut request = CorrectionRequest {
+        page_id: String::new(),
+        user_id: user.id.clone(),
+        user_name: user.display_name().to_string(),
+        date: submission.date,
+        reason: submission.reason,
+        requested_at: jst::now(),
+        status: ApprovalStatus::Pending,
+        detail: submission.detail,
+        approver_id: None,
+        applied_page_ids: Vec::new(),
+    };
+    let lines = correction::describe_detail(&request.detail).join("\n");
+
+    request.page_id = notion_client.create_correction_request(&request).await?;
+
+    let message = correction::build_approval_message(&request);
+    for approver in approvers {
+        slack_client.post_message(approver, &message).await?;
+    }
+
+    let text = format!(
+        "{} の打刻修正を申請しました。承認されると打刻に反映されます\n{}\n理由: {}",
+        request.date, lines, request.reason
+    );
+    slack_client.post_message(&user.id, &ephemeral_response(text)).await?;
+
+    Ok(())
+}
+
+/// Approve / reject button on a correction request DM. `command.text` holds the request page id.
+async fn handle_correction_decision(
+    config: &Config,
+    command: &SlackCommand,
+    status: ApprovalStatus,
+) -> Result<(), Error> {
+    if !config.is_approver(&command.user_id) {
+        let response = ephemeral_response("打刻修正の承認は承認者のみ行えます".to_string());
+        return deliver(config, command, &response).await;
+    }
+
+    let notion_client = config.notion_client()?;
+    let mut request = notion_client.get_correction_request(&command.text).await?;
+
+    if let Some(text) = already_decided(&request, &command.user_id) {
+        return replace_original(&command.response_url, &text).await;
+    }
+
+    if request.user_id == command.user_id {
+        let response = ephemeral_response("自分の打刻修正の申請は承認・却下できません".to_string());
+        return deliver(config, command, &response).await;
+    }
+
+    // 先に「処理中」にして自分の名前で押さえてから反映する。同時に押された場合は
+    // 後から書き込んだ方が承認者として残るため、読み直して自分が押さえたか確かめる
+    if request.status == ApprovalStatus::Pending {
+        notion_client
+            .claim_correction_request(&request.page_id, &command.user_id)
+            .await?;
+        request = notion_client.get_correction_request(&request.page_id).await?;
+        if let Some(text) = already_decided(&request, &command.user_id) {
+            return replace_original(&command.response_url, &text).await;
+        }
+    }
+
+    if status == ApprovalStatus::Approved {
+        apply_correction(&notion_client, &request).await?;
+    }
+    let now = jst::now();
+    notion_client
+        .decide_correction_request(&request.page_id, status, &command.user_id, &now)
+        .await?;
+
+    let lines = correction::describe_detail(&request.detail).join("\n");
+    let text = format!(
+        "<@{}> さんの {} の打刻修正: {}（<@{}>）\n{}\n理由: {}",
+        request.user_id,
+        request.date,
+        status.label(),
+        command.user_id,
+        lines,
+        request.reason
+    );
+    replace_original(&command.response_url, &text).await?;
+
+    let verdict = match status {
+        ApprovalStatus::Approved => "承認され、打刻に反映しました",
+        _ => "却下されました",
+    };
+    let slack_client = config.workspace.slack_client()?;
+    slack_client
+        .post_message(
+            &request.user_id,
+            &ephemeral_response(format!("{} の打刻修正が{}\n{}", request.date, verdict, lines)),
+        )
+        .await?;
+    if status == ApprovalStatus::Approved {
+        refresh_home(config, &request.user_id).await;
+    }
+
+    Ok(())
+}
+
+/// Describes why `approver_id` can no longer act on the request: it was already decided,
+/// or another approver is in the middle of deciding it. A request this approver claimed
+/// earlier (e.g. a redelivered message) can be finished.
+fn already_decided(request: &CorrectionRequest, approver_id: &str) -> Option<String> {
+    let decided_by = request
+        .approver_id
+        .as_deref()
+        .map(|id| format!("（<@{}>）", id))
+        .unwrap_or_default();
+    match request.status {
+        ApprovalStatus::Pending => None,
+        ApprovalStatus::Processing if request.approver_id.as_deref() == Some(approver_id) => None,
+        ApprovalStatus::Processing => Some(format!("この申請は他の承認者が処理中です{}", decided_by)),
+        _ => Some(format!("この申請は既に{}です{}", request.status.label(), decided_by)),
+    }
+}
+
+/// Writes an approved correction to the punches: changed times keep their previous value
+/// on the page, and added punches carry the correction reason. Added punches are recorded
+/// on the request as they are created, so a retry after a failure skips the ones already
+/// written; re-applying a time change just writes the same values again.
+async fn apply_correction(notion_client: &notion::NotionClient, request: &CorrectionRequest) -> Result<(), Error> {
+    let now = jst::now();
+
+    for change in &request.detail.changes {
+        notion_client
+            .correct_record(&change.page_id, &change.original, &change.corrected, &request.reason, &now)
+            .await?;
+    }
+
+    let mut applied_page_ids = request.applied_page_ids.clone();
+    for addition in request.detail.additions.iter().skip(applied_page_ids.len()) {
+        let record = AttendanceRecord {
+            user_id: request.user_id.clone(),
+            user_name: request.user_name.clone(),
+            action: addition.action.clone(),
+            timestamp: addition.timestamp,
+            date: request.date.clone(),
+            correction_reason: Some(request.reason.clone()),
+            location: None,
+            note: None,
+            recorded_by: None,
+        };
+        applied_page_ids.push(notion_client.create_attendance_record(&record).await?);
+        notion_client
+            .record_applied_pages(&request.page_id, &applied_page_ids)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn handle_shortcut(
+    config: &Config,
+    user: &InteractionUser,
+    team: &InteractionTeam,
+    callback_id: &str,
+    trigger_id: &str,
+) -> Result<(), Error> {
+    // ショートカットの callback_id は `attendance_<コマンド>` の形式 (例: attendance_in)
+    let Some(text) = callback_id.strip_prefix("attendance_") else {
+        tracing::warn!("Unhandled shortcut: {}", callback_id);
+        return Ok(());
+    };
+
+    let command = SlackCommand {
+        token: String::new(),
+        team_id: team.id.clone(),
+        team_domain: team.domain.clone(),
+        channel_id: String::new(),
+        channel_name: String::new(),
+        user_id: user.id.clone(),
+        user_name: user.display_name().to_string(),
+        command: String::new(),
         text: text.to_string(),
+        response_url: String::new(),
+        trigger_id: trigger_id.to_string(),
     };
+
+    // ショートカットには response_url が無いため、DMで結果を返す
+    let response = handle_command(config, &command).await?;
+    deliver(config, &command, &response).await?;
+
+    Ok(())
+}
+
+async fn send_delayed_response(response_url: &str, response: &SlackResponse) -> Result<(), Error> {
+    let client = reqwest::Client::new();
     
     client
         .post(response_url)
-        .json(&response)
+        .json(response)
         .send()
         .await?;
     
     Ok(())
 }
 
-async fn handle_attendance(command: &SlackCommand) -> Result<String, Error> {
-    let action = match slack::parse_command_text(&command.text) {
-        Ok(a) => a,
-        Err(e) => return Ok(e),
+/// Overwrites the message whose button was clicked, so a decided request loses its buttons.
+async fn replace_original(response_url: &str, text: &str) -> Result<(), Error> {
+    let client = reqwest::Client::new();
+
+    client
+        .post(response_url)
+        .json(&serde_json::json!({ "replace_original": true, "text": text }))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+async fn handle_event(config: &Config, event: &SlackEvent) -> Result<(), Error> {
+    if let SlackEvent::AppHomeOpened { user, tab } = event {
+        if tab == "home" {
+            publish_home(config, user).await?;
+        }
+        return Ok(());
+    }
+
+    let Some((user_id, channel_id, action)) = events::match_event(&config.event_rules, event) else {
+        return Ok(());
     };
 
-    let notion_client = notion::NotionClient::new(
-        std::env::var("NOTION_API_KEY")?,
-        std::env::var("NOTION_DATABASE_ID")?,
-    );
+    let slack_client = config.workspace.slack_client()?;
+    let user_name = slack_client.get_user_name(&user_id).await?;
+
+    // イベント経由の打刻は本人にだけ結果を表示する
+    let response = record_punch(config, &user_id, &user_name, &channel_id, action.into()).await?;
+    slack_client.post_ephemeral(&channel_id, &user_id, &response).await?;
+
+    Ok(())
+}
 
-    let now = Utc::now();
-    let jst_offset = FixedOffset::east_opt(9 * 3600).unwrap(); // JST = UTC+9
-    let jst = now.with_timezone(&jst_offset);
+async fn record_punch(
+    config: &Config,
+    user_id: &str,
+    user_name: &str,
+    channel_id: &str,
+    punch: slack::PunchRequest,
+) -> Result<SlackResponse, Error> {
+    let action = punch.action;
+    let notion_client = config.notion_client()?;
+
+    let jst = jst::now();
     
     let record = AttendanceRecord {
-        user_id: command.user_id.clone(),
-        user_name: command.user_name.clone(),
+        user_id: user_id.to_string(),
+        user_name: user_name.to_string(),
         action: action.clone(),
         timestamp: jst, // JSTの時刻をそのまま保存
-        date: jst.format("%Y-%m-%d").to_string(),
+        date: punch.work_date.unwrap_or(jst.date_naive()).format("%Y-%m-%d").to_string(),
+        correction_reason: None,
+        location: punch.location.clone(),
+        note: punch.note.clone(),
+        recorded_by: None,
     };
 
     notion_client.create_attendance_record(&record).await?;
+    refresh_home(config, user_id).await;
 
     let action_text = match action {
         AttendanceAction::In => "出勤",
@@ -86,26 +533,457 @@ async fn handle_attendance(command: &SlackCommand) -> Result<String, Error> {
         AttendanceAction::Out => "退勤",
     };
 
-    Ok(format!(
+    let mut text = format!(
         "{} さんが {} しました ({})",
-        command.user_name,
+        user_name,
         action_text,
         jst.format("%Y-%m-%d %H:%M:%S")
-    ))
+    );
+    if let Some(location) = &punch.location {
+        text.push_str(&format!(" 勤務場所: {}", location.label()));
+    }
+    if let Some(note) = &punch.note {
+        text.push_str(&format!("\nメモ: {}", note));
+    }
+    if let Some(work_date) = punch.work_date.filter(|date| *date != jst.date_naive()) {
+        text.push_str(&format!("\n{} の勤務として記録しました", work_date));
+    }
+
+    // 次に押せるボタンを添えて返す
+    let buttons = slack::action_buttons(&action);
+    let mut blocks = vec![Block::Section {
+        text: slack::mrkdwn(&text),
+    }];
+    if !buttons.is_empty() {
+        blocks.push(Block::Actions { elements: buttons });
+    }
+
+    // 打刻結果はチャンネルごとの設定に従い、それ以外は本人にだけ表示する
+    Ok(SlackResponse {
+        response_type: config.punch_response_type(channel_id).to_string(),
+        text,
+        blocks: Some(blocks),
+    })
 }
 
-async fn handle_report(command: &SlackCommand) -> Result<String, Error> {
-    let notion_client = notion::NotionClient::new(
-        std::env::var("NOTION_API_KEY")?,
-        std::env::var("NOTION_DATABASE_ID")?,
+async fn handle_report(
+    config: &Config,
+    command: &SlackCommand,
+    month: Option<(i32, u32)>,
+) -> Result<SlackResponse, Error> {
+    let notion_client = config.notion_client()?;
+
+    let (year, month) = month.unwrap_or_else(current_month);
+    let punches = notion_client
+        .get_monthly_records(&command.user_id, year, month)
+        .await?;
+    let period = format!("{}-{:02}", year, month);
+    let leaves = notion_client.get_leaves(Some(&command.user_id), Some(&period)).await?;
+    let switches = notion_client.get_task_switches(Some(&command.user_id), &period).await?;
+    let corrections = notion_client.get_correction_requests(Some(&command.user_id), &period).await?;
+    let prior_punches = prior_week_punches(&notion_client, Some(&command.user_id), year, month).await?;
+    let today = today();
+    let days = report::summarize_days(&punches, &config.work_policy, Some(&today));
+    let prior_days = report::summarize_days(&prior_punches, &config.work_policy, None);
+    let labor = labor::evaluate(&config.labor_rules, &days, &prior_days);
+    let attendance = calendar::month_attendance(&config.calendar, year, month, &days, &leaves, &today);
+    let mut report = report::format_monthly_report(
+        year,
+        month,
+        &days,
+        &labor,
+        &config.work_policy.rounding,
+        &attendance,
+        &task::allocate(&days, &switches),
     );
+    if let Some(section) = correction::format_requests(&corrections, false) {
+        report.push_str(&format!("\n\n{}", section));
+    }
+
+    Ok(ephemeral_response(format!("{} さんの月次レポート:\n{}", command.user_name, report)))
+}
+
+/// 月初を含む週の前月分の打刻。週40時間の判定にだけ使う。`user_id` が `None` なら全員分
+async fn prior_week_punches(
+    notion_client: &notion::NotionClient,
+    user_id: Option<&str>,
+    year: i32,
+    month: u32,
+) -> Result<Vec<PunchRecord>, Error> {
+    let dates = labor::prior_week_dates(year, month);
+    if dates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(match user_id {
+        Some(user_id) => notion_client.get_records_for_dates(user_id, &dates).await?,
+        None => notion_client.get_all_records_for_dates(&dates).await?,
+    })
+}
+
+async fn handle_team_report(
+    config: &Config,
+    command: &SlackCommand,
+    month: Option<(i32, u32)>,
+) -> Result<SlackResponse, Error> {
+    if !config.is_admin(&command.user_id) {
+        return Ok(ephemeral_response("チームレポートは管理者のみ利用できます".to_string()));
+    }
+
+    let notion_client = config.notion_client()?;
+
+    let (year, month) = month.unwrap_or_else(current_month);
+    let mut punches = notion_client.get_all_monthly_records(year, month).await?;
+    punches.extend(prior_week_punches(&notion_client, None, year, month).await?);
+    let period = format!("{}-{:02}", year, month);
+    let leaves = notion_client.get_leaves(None, Some(&period)).await?;
+    let switches = notion_client.get_task_switches(None, &period).await?;
+    let corrections = notion_client.get_correction_requests(None, &period).await?;
+    let members = report::summarize_members(config, year, month, &punches, &leaves, &switches, &today());
+    let mut report = report::format_team_report(year, month, &members);
+    if let Some(section) = correction::format_requests(&corrections, true) {
+        report.push_str(&format!("\n\n{}", section));
+    }
+
+    if config.team_report_by_dm {
+        let slack_client = config.workspace.slack_client()?;
+        slack_client
+            .post_message(&command.user_id, &ephemeral_response(report))
+            .await?;
+        return Ok(ephemeral_response(format!("{}年{}月のチームレポートをDMで送信しました", year, month)));
+    }
+
+    Ok(ephemeral_response(report))
+}
+
+/// Builds a per-day spreadsheet from the day summaries and sends it to the caller by DM.
+async fn handle_export(
+    config: &Config,
+    command: &SlackCommand,
+    scope: slack::ReportScope,
+    user_id: Option<String>,
+    month: Option<(i32, u32)>,
+    format: export::ExportFormat,
+) -> Result<SlackResponse, Error> {
+    if scope == slack::ReportScope::Team && !config.is_admin(&command.user_id) {
+        return Ok(ephemeral_response("チームのエクスポートは管理者のみ利用できます".to_string()));
+    }
+    if user_id.as_ref().is_some_and(|id| *id != command.user_id) && !config.is_admin(&command.user_id) {
+        return Ok(ephemeral_response("他のユーザーのエクスポートは管理者のみ利用できます".to_string()));
+    }
+
+    let notion_client = config.notion_client()?;
+    let slack_client = config.workspace.slack_client()?;
+
+    let (year, month) = month.unwrap_or_else(current_month);
+    let (punches, target) = match (scope, user_id) {
+        (slack::ReportScope::Team, _) => (
+            notion_client.get_all_monthly_records(year, month).await?,
+            "team".to_string(),
+        ),
+        (slack::ReportScope::Own, Some(user_id)) if user_id != command.user_id => (
+            notion_client.get_monthly_records(&user_id, year, month).await?,
+            slack_client.get_user_name(&user_id).await?,
+        ),
+        (slack::ReportScope::Own, _) => (
+            notion_client.get_monthly_records(&command.user_id, year, month).await?,
+            command.user_name.clone(),
+        ),
+    };
+
+    let rows = export::build_rows(&punches, &config.work_policy);
+    let content = export::render(&rows, format)?;
+    let filename = format!("attendance_{}_{}-{:02}.{}", target, year, month, format.extension());
 
-    let now = Local::now();
-    let report = notion_client
-        .get_monthly_report(&command.user_id, now.year(), now.month())
+    slack_client
+        .upload_file_to_dm(
+            &command.user_id,
+            &filename,
+            content,
+            &format!("{}年{}月の勤怠データ（{}行）", year, month, rows.len()),
+        )
         .await?;
 
-    Ok(format!("{} さんの月次レポート:\n{}", command.user_name, report))
+    Ok(ephemeral_response(format!("{} をDMに送信しました", filename)))
+}
+
+async fn handle_leave(
+    config: &Config,
+    command: &SlackCommand,
+    date: NaiveDate,
+    leave_type: LeaveType,
+) -> Result<SlackResponse, Error> {
+    if let Some(reason) = config.calendar.day_off_reason(date) {
+        return Ok(ephemeral_response(format!(
+            "{} は所定休日（{}）のため休暇は登録できません",
+            date, reason
+        )));
+    }
+
+    let notion_client = config.notion_client()?;
+
+    let date = date.format("%Y-%m-%d").to_string();
+    let existing = notion_client.get_leaves(Some(&command.user_id), Some(&date)).await?;
+    if let Some(leave) = existing.iter().find(|leave| leave.leave_type.conflicts_with(&leave_type)) {
+        return Ok(ephemeral_response(format!(
+            "{} には既に{}が登録されています",
+            date,
+            leave.leave_type.label()
+        )));
+    }
+
+    notion_client
+        .create_leave_record(&command.user_id, &command.user_name, &date, leave_type)
+        .await?;
+
+    let mut text = format!("{} に{}（{}日）を登録しました", date, leave_type.label(), leave_type.days());
+    if leave_type.is_paid() {
+        let balance = leave_balance(&notion_client, &command.user_id).await?;
+        text.push_str(&format!("\n{}", balance.format()));
+    }
+
+    Ok(ephemeral_response(text))
+}
+
+async fn handle_leave_balance(config: &Config, command: &SlackCommand) -> Result<SlackResponse, Error> {
+    let notion_client = config.notion_client()?;
+    let balance = leave_balance(&notion_client, &command.user_id).await?;
+
+    Ok(ephemeral_response(format!("{} さんの{}", command.user_name, balance.format())))
+}
+
+async fn leave_balance(notion_client: &notion::NotionClient, user_id: &str) -> Result<leave::LeaveBalance, Error> {
+    let grants = notion_client.get_leave_grants(user_id).await?;
+    let leaves = notion_client.get_leaves(Some(user_id), None).await?;
+    let today = NaiveDate::parse_from_str(&today(), "%Y-%m-%d")?;
+
+    Ok(leave::compute_balance(&grants, &leaves, today))
+}
+
+async fn handle_leave_grant(
+    config: &Config,
+    command: &SlackCommand,
+    user_id: &str,
+    date: NaiveDate,
+    days: f64,
+) -> Result<SlackResponse, Error> {
+    if !config.is_admin(&command.user_id) {
+        return Ok(ephemeral_response("有給休暇の付与は管理者のみ利用できます".to_string()));
+    }
+
+    let notion_client = config.notion_client()?;
+    let slack_client = config.workspace.slack_client()?;
+
+    let grant = LeaveGrant {
+        user_id: user_id.to_string(),
+        user_name: slack_client.get_user_name(user_id).await?,
+        date: date.format("%Y-%m-%d").to_string(),
+        days,
+    };
+    notion_client.create_leave_grant(&grant).await?;
+    let balance = leave_balance(&notion_client, user_id).await?;
+
+    Ok(ephemeral_response(format!(
+        "<@{}> さんに {} 付で有給休暇を{}日付与しました\n{}",
+        user_id,
+        grant.date,
+        days,
+        balance.format()
+    )))
+}
+
+/// Records a punch on behalf of another user. The admin is stored as 登録者 and the
+/// user is told about the change by DM.
+async fn handle_admin_punch(
+    config: &Config,
+    command: &SlackCommand,
+    user_id: &str,
+    action: AttendanceAction,
+    time: NaiveTime,
+    date: Option<NaiveDate>,
+    reason: Option<String>,
+) -> Result<SlackResponse, Error> {
+    if !config.is_admin(&command.user_id) {
+        return Ok(ephemeral_response("代理打刻は管理者のみ利用できます".to_string()));
+    }
+
+    let now = jst::now();
+    let date = date.unwrap_or_else(|| now.date_naive());
+    let timestamp = jst::datetime(date, time)?;
+    if timestamp > now {
+        return Ok(ephemeral_response(format!(
+            "未来の時刻は登録できません: {}",
+            timestamp.format("%Y-%m-%d %H:%M")
+        )));
+    }
+
+    let notion_client = config.notion_client()?;
+    let slack_client = config.workspace.slack_client()?;
+
+    let reason = reason.unwrap_or_else(|| "管理者による代理登録".to_string());
+    let record = AttendanceRecord {
+        user_id: user_id.to_string(),
+        user_name: slack_client.get_user_name(user_id).await?,
+        action: action.clone(),
+        timestamp,
+        date: date.format("%Y-%m-%d").to_string(),
+        correction_reason: Some(reason.clone()),
+        location: None,
+        note: None,
+        recorded_by: Some(command.user_id.clone()),
+    };
+    notion_client.create_attendance_record(&record).await?;
+
+    let summary = format!(
+        "{} {} {}（理由: {}）",
+        record.date,
+        action.label(),
+        timestamp.format("%H:%M"),
+        reason
+    );
+    slack_client
+        .post_message(
+            user_id,
+            &ephemeral_response(format!("<@{}> さんがあなたの打刻を登録しました: {}", command.user_id, summary)),
+        )
+        .await?;
+    refresh_home(config, user_id).await;
+
+    Ok(ephemeral_response(format!("<@{}> さんの打刻を登録しました: {}", user_id, summary)))
+}
+
+/// Lists audit log entries for a day or month, newest first.
+async fn handle_audit(
+    config: &Config,
+    command: &SlackCommand,
+    user_id: Option<String>,
+    period: Option<String>,
+) -> Result<SlackResponse, Error> {
+    if !config.is_admin(&command.user_id) {
+        return Ok(ephemeral_response("監査ログは管理者のみ利用できます".to_string()));
+    }
+    let Some(notion_client) = config.workspace.audit_client() else {
+        return Ok(ephemeral_response(
+            "監査ログのデータベース（NOTION_AUDIT_DATABASE_ID）が設定されていません".to_string(),
+        ));
+    };
+    let period = period.unwrap_or_else(today);
+    let entries = notion_client.get_audit_entries(user_id.as_deref(), &period).await?;
+
+    Ok(ephemeral_response(audit::format_entries(&entries, &period, 30)))
+}
+
+/// Switches the project the rest of today's session is allocated to.
+async fn handle_task(config: &Config, command: &SlackCommand, project: String) -> Result<SlackResponse, Error> {
+    let notion_client = config.notion_client()?;
+
+    let now = jst::now();
+    let today = now.format("%Y-%m-%d").to_string();
+
+    let punches = notion_client.get_daily_records(&command.user_id, &today).await?;
+    let status = report::current_status(&punches);
+    if !matches!(status, report::WorkStatus::Working { .. } | report::WorkStatus::OnBreak { .. }) {
+        return Ok(ephemeral_response(format!(
+            "プロジェクトは勤務中にだけ切り替えられます（現在の状態: {}）",
+            status.describe()
+        )));
+    }
+
+    let switch = TaskSwitch {
+        user_id: command.user_id.clone(),
+        user_name: command.user_name.clone(),
+        date: today,
+        timestamp: now,
+        project,
+    };
+    notion_client.create_task_switch(&switch).await?;
+
+    Ok(ephemeral_response(format!(
+        "{} から {} の作業として記録します",
+        now.format("%H:%M"),
+        switch.project
+    )))
+}
+
+/// This month in JST, so reports run just after midnight on the 1st cover the new month.
+fn current_month() -> (i32, u32) {
+    let today = jst::today();
+    (today.year(), today.month())
+}
+
+/// Today's date in JST (YYYY-MM-DD), matching the Notion 日付 property.
+fn today() -> String {
+    jst::today().format("%Y-%m-%d").to_string()
+}
+
+async fn handle_status(config: &Config, command: &SlackCommand) -> Result<SlackResponse, Error> {
+    let notion_client = config.notion_client()?;
+
+    let today = today();
+    let punches = notion_client.get_daily_records(&command.user_id, &today).await?;
+    let status = report::current_status(&punches);
+
+    let mut text = format!("{} さんの現在の状態: {}", command.user_name, status.describe());
+    if let Some(day) = report::summarize_days(&punches, &config.work_policy, Some(&today)).first() {
+        text.push_str(&format!("\n本日の休憩: {}", report::format_duration(day.break_minutes())));
+        if day.open_breaks() > 0 {
+            text.push_str(&format!(
+                "\n⚠️ 休憩戻りが記録されていない休憩が{}件あります（次の打刻の時刻で休憩終了として計算しています）。`/attendance fix` で修正してください",
+                day.open_breaks()
+            ));
+        }
+    }
+
+    Ok(ephemeral_response(text))
+}
+
+/// Rebuilds the user's App Home dashboard from this week's punches.
+async fn publish_home(config: &Config, user_id: &str) -> Result<(), Error> {
+    let notion_client = config.notion_client()?;
+
+    let now = jst::now();
+    let today = now.date_naive();
+
+    // 今週（月曜〜今日）の日付
+    let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let dates: Vec<String> = monday
+        .iter_days()
+        .take_while(|d| *d <= today)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .collect();
+
+    let punches = notion_client.get_records_for_dates(user_id, &dates).await?;
+    let today_str = today.format("%Y-%m-%d").to_string();
+    let today_punches: Vec<_> = punches.iter().filter(|p| p.date == today_str).cloned().collect();
+    let recent = &punches[punches.len().saturating_sub(5)..];
+
+    let view = home::build_home_view(
+        &report::current_status(&today_punches),
+        &report::summarize_days(&punches, &config.work_policy, Some(&today_str)),
+        recent,
+        &now.format("%Y-%m-%d %H:%M").to_string(),
+    );
+
+    let slack_client = config.workspace.slack_client()?;
+    slack_client.publish_view(user_id, &view).await?;
+
+    Ok(())
+}
+
+/// Home tab refresh after a punch. A failure here must not fail the punch itself.
+async fn refresh_home(config: &Config, user_id: &str) {
+    if let Err(e) = publish_home(config, user_id).await {
+        tracing::warn!("Failed to refresh App Home for {}: {}", user_id, e);
+    }
+}
+
+/// Reports, status and errors are only shown to the user who ran the command.
+fn ephemeral_response(text: String) -> SlackResponse {
+    SlackResponse {
+        response_type: "ephemeral".to_string(),
+        text,
+        blocks: None,
+    }
 }
 
 #[tokio::main]
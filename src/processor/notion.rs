@@ -1,11 +1,34 @@
+use crate::audit::AuditEntry;
 use crate::types::*;
 use reqwest::Client;
-use chrono::Timelike;
+use std::sync::{Arc, Mutex};
+
+/// Notionのテキスト要素1つに入る文字数の上限（UTF-16単位）
+pub const MAX_TEXT_LENGTH: usize = 2000;
+
+/// 1件のメッセージの処理中に作成・更新したページID（監査ログ用）。
+/// クローンは同じ記録を共有する
+#[derive(Debug, Clone, Default)]
+pub struct PageLog(Arc<Mutex<Vec<String>>>);
+
+impl PageLog {
+    fn record(&self, page_id: &str) {
+        if let Ok(mut pages) = self.0.lock() {
+            pages.push(page_id.to_string());
+        }
+    }
+
+    /// 記録したページIDを取り出して空にする
+    pub fn take(&self) -> Vec<String> {
+        self.0.lock().map(|mut pages| std::mem::take(&mut *pages)).unwrap_or_default()
+    }
+}
 
 pub struct NotionClient {
     client: Client,
     api_key: String,
     database_id: String,
+    page_log: Option<PageLog>,
 }
 
 impl NotionClient {
@@ -14,13 +37,21 @@ impl NotionClient {
             client: Client::new(),
             api_key,
             database_id,
+            page_log: None,
         }
     }
 
+    /// 作成・更新したページIDを `page_log` に記録する
+    pub fn with_page_log(mut self, page_log: PageLog) -> Self {
+        self.page_log = Some(page_log);
+        self
+    }
+
+    /// 打刻を登録し、そのページIDを返す
     pub async fn create_attendance_record(
         &self,
         record: &AttendanceRecord,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
         let properties = NotionProperties {
             user_id: NotionTitle {
                 title: vec![NotionTextContent {
@@ -29,37 +60,76 @@ impl NotionClient {
                     },
                 }],
             },
-            user_name: NotionRichText {
-                rich_text: vec![NotionTextContent {
+            user_name: rich_text(&record.user_name),
+            action: NotionSelect {
+                select: NotionOption {
+                    name: record.action.label().to_string(),
+                },
+            },
+            timestamp: date_value(&record.timestamp),
+            date: rich_text(&record.date),
+            correction_reason: record.correction_reason.as_deref().map(rich_text),
+            granted_days: None,
+            location: record.location.as_ref().map(|location| NotionSelect {
+                select: NotionOption { name: location.label() },
+            }),
+            project: None,
+            note: record.note.as_deref().map(rich_text),
+            recorded_by: record.recorded_by.as_deref().map(rich_text),
+            approval_status: None,
+            correction_detail: None,
+        };
+
+        self.create_page(properties).await
+    }
+
+    /// 休暇・欠勤を1日1ページとして登録する。タイムスタンプには日付だけを入れる
+    pub async fn create_leave_record(
+        &self,
+        user_id: &str,
+        user_name: &str,
+        date: &str,
+        leave_type: LeaveType,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let properties = NotionProperties {
+            user_id: NotionTitle {
+                title: vec![NotionTextContent {
                     text: NotionText {
-                        content: record.user_name.clone(),
+                        content: user_id.to_string(),
                     },
                 }],
             },
+            user_name: rich_text(user_name),
             action: NotionSelect {
                 select: NotionOption {
-                    name: match &record.action {
-                        AttendanceAction::In => "出勤".to_string(),
-                        AttendanceAction::Break => "休憩入り".to_string(),
-                        AttendanceAction::Back => "休憩戻り".to_string(),
-                        AttendanceAction::Out => "退勤".to_string(),
-                    },
+                    name: leave_type.label().to_string(),
                 },
             },
             timestamp: NotionDate {
                 date: NotionDateValue {
-                    start: record.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    start: date.to_string(),
                 },
             },
-            date: NotionRichText {
-                rich_text: vec![NotionTextContent {
-                    text: NotionText {
-                        content: record.date.clone(),
-                    },
-                }],
-            },
+            date: rich_text(date),
+            correction_reason: None,
+            granted_days: None,
+            location: None,
+            project: None,
+            note: None,
+            recorded_by: None,
+            approval_status: None,
+            correction_detail: None,
         };
 
+        self.create_page(properties).await?;
+        Ok(())
+    }
+
+    /// ページを作成し、そのページIDを返す
+    async fn create_page<P: serde::Serialize>(
+        &self,
+        properties: P,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
         let request_body = NotionPageRequest {
             parent: NotionParent {
                 database_id: self.database_id.clone(),
@@ -82,22 +152,116 @@ impl NotionClient {
             return Err(format!("Notion API error: {}", error_text).into());
         }
 
-        Ok(())
+        let page: serde_json::Value = response.json().await?;
+        let page_id = page["id"].as_str().ok_or("Notion API returned no page id")?.to_string();
+        if let Some(page_log) = &self.page_log {
+            page_log.record(&page_id);
+        }
+        Ok(page_id)
+    }
+
+    /// 指定日の打刻をページIDつきで時刻順に取得する
+    pub async fn get_daily_records(
+        &self,
+        user_id: &str,
+        date: &str,
+    ) -> Result<Vec<PunchRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        self.get_records_for_dates(user_id, &[date.to_string()]).await
     }
 
-    pub async fn get_monthly_report(
+    /// 複数日の打刻を取得する（週の集計など月をまたぐ期間用）
+    pub async fn get_records_for_dates(
         &self,
         user_id: &str,
+        dates: &[String],
+    ) -> Result<Vec<PunchRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        let date_filters: Vec<_> = dates
+            .iter()
+            .map(|date| {
+                serde_json::json!({
+                    "property": "日付",
+                    "rich_text": {
+                        "equals": date
+                    }
+                })
+            })
+            .collect();
+
+        let filter = serde_json::json!({
+            "and": [
+                {
+                    "property": "ユーザーID",
+                    "title": {
+                        "equals": user_id
+                    }
+                },
+                {
+                    "or": date_filters
+                }
+            ]
+        });
+
+        self.query_punches(filter).await
+    }
+
+    /// 複数日の全ユーザーの打刻を時刻順に取得する
+    pub async fn get_all_records_for_dates(
+        &self,
+        dates: &[String],
+    ) -> Result<Vec<PunchRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        let date_filters: Vec<_> = dates
+            .iter()
+            .map(|date| {
+                serde_json::json!({
+                    "property": "日付",
+                    "rich_text": {
+                        "equals": date
+                    }
+                })
+            })
+            .collect();
+
+        self.query_punches(serde_json::json!({ "or": date_filters })).await
+    }
+
+    /// 指定日の全ユーザーの打刻を時刻順に取得する
+    pub async fn get_all_records_on(
+        &self,
+        date: &str,
+    ) -> Result<Vec<PunchRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        let filter = serde_json::json!({
+            "property": "日付",
+            "rich_text": {
+                "equals": date
+            }
+        });
+
+        self.query_punches(filter).await
+    }
+
+    /// 指定月の全ユーザーの打刻を時刻順に取得する
+    pub async fn get_all_monthly_records(
+        &self,
         year: i32,
         month: u32,
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        let _start_date = format!("{}-{:02}-01", year, month);
-        let _end_date = if month == 12 {
-            format!("{}-01-01", year + 1)
-        } else {
-            format!("{}-{:02}-01", year, month + 1)
-        };
+    ) -> Result<Vec<PunchRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        let filter = serde_json::json!({
+            "property": "日付",
+            "rich_text": {
+                "contains": format!("{}-{:02}", year, month)
+            }
+        });
 
+        self.query_punches(filter).await
+    }
+
+    /// 指定月の打刻を時刻順に取得する
+    pub async fn get_monthly_records(
+        &self,
+        user_id: &str,
+        year: i32,
+        month: u32,
+    ) -> Result<Vec<PunchRecord>, Box<dyn std::error::Error + Send + Sync>> {
         let filter = serde_json::json!({
             "and": [
                 {
@@ -115,6 +279,165 @@ impl NotionClient {
             ]
         });
 
+        self.query_punches(filter).await
+    }
+
+    /// 指定期間（「日付」の前方一致。例: `2026` や `2026-10`。None なら全期間）の休暇を日付順に取得する。
+    /// `user_id` が None なら全ユーザー分
+    pub async fn get_leaves(
+        &self,
+        user_id: Option<&str>,
+        period: Option<&str>,
+    ) -> Result<Vec<LeaveRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        let labels: Vec<&str> = LeaveType::all().iter().map(|t| t.label()).collect();
+        let results = self.query_by_actions(&labels, user_id, period).await?;
+
+        Ok(results.iter().filter_map(parse_leave).collect())
+    }
+
+    /// ユーザーの有給休暇の付与を付与日順に取得する
+    pub async fn get_leave_grants(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<LeaveGrant>, Box<dyn std::error::Error + Send + Sync>> {
+        let results = self.query_by_actions(&[LEAVE_GRANT_LABEL], Some(user_id), None).await?;
+
+        Ok(results.iter().filter_map(parse_grant).collect())
+    }
+
+    /// 有給休暇の付与を登録する。「付与日数」に日数を入れる
+    pub async fn create_leave_grant(&self, grant: &LeaveGrant) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let properties = NotionProperties {
+            user_id: NotionTitle {
+                title: vec![NotionTextContent {
+                    text: NotionText {
+                        content: grant.user_id.clone(),
+                    },
+                }],
+            },
+            user_name: rich_text(&grant.user_name),
+            action: NotionSelect {
+                select: NotionOption {
+                    name: LEAVE_GRANT_LABEL.to_string(),
+                },
+            },
+            timestamp: NotionDate {
+                date: NotionDateValue {
+                    start: grant.date.clone(),
+                },
+            },
+            date: rich_text(&grant.date),
+            correction_reason: None,
+            granted_days: Some(NotionNumber { number: grant.days }),
+            location: None,
+            project: None,
+            note: None,
+            recorded_by: None,
+            approval_status: None,
+            correction_detail: None,
+        };
+
+        self.create_page(properties).await?;
+        Ok(())
+    }
+
+    /// プロジェクトの切り替えを記録する
+    pub async fn create_task_switch(&self, switch: &TaskSwitch) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let properties = NotionProperties {
+            user_id: NotionTitle {
+                title: vec![NotionTextContent {
+                    text: NotionText {
+                        content: switch.user_id.clone(),
+                    },
+                }],
+            },
+            user_name: rich_text(&switch.user_name),
+            action: NotionSelect {
+                select: NotionOption {
+                    name: TASK_SWITCH_LABEL.to_string(),
+                },
+            },
+            timestamp: date_value(&switch.timestamp),
+            date: rich_text(&switch.date),
+            correction_reason: None,
+            granted_days: None,
+            location: None,
+            project: Some(rich_text(&switch.project)),
+            note: None,
+            recorded_by: None,
+            approval_status: None,
+            correction_detail: None,
+        };
+
+        self.create_page(properties).await?;
+        Ok(())
+    }
+
+    /// 指定期間（「日付」の前方一致）のプロジェクト切り替えを時刻順に取得する。`user_id` が None なら全ユーザー分
+    pub async fn get_task_switches(
+        &self,
+        user_id: Option<&str>,
+        period: &str,
+    ) -> Result<Vec<TaskSwitch>, Box<dyn std::error::Error + Send + Sync>> {
+        let results = self.query_by_actions(&[TASK_SWITCH_LABEL], user_id, Some(period)).await?;
+
+        Ok(results.iter().filter_map(parse_task_switch).collect())
+    }
+
+    /// 「アクション」が指定のいずれかであるページを日付順に取得する
+    async fn query_by_actions(
+        &self,
+        labels: &[&str],
+        user_id: Option<&str>,
+        period: Option<&str>,
+    ) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
+        let action_filters: Vec<_> = labels
+            .iter()
+            .map(|label| {
+                serde_json::json!({
+                    "property": "アクション",
+                    "select": {
+                        "equals": label
+                    }
+                })
+            })
+            .collect();
+
+        let mut conditions = vec![serde_json::json!({ "or": action_filters })];
+        if let Some(period) = period {
+            conditions.push(serde_json::json!({
+                "property": "日付",
+                "rich_text": {
+                    "starts_with": period
+                }
+            }));
+        }
+        if let Some(user_id) = user_id {
+            conditions.push(serde_json::json!({
+                "property": "ユーザーID",
+                "title": {
+                    "equals": user_id
+                }
+            }));
+        }
+
+        let request_body = serde_json::json!({
+            "filter": { "and": conditions },
+            "sorts": [
+                {
+                    "property": "タイムスタンプ",
+                    "direction": "ascending"
+                }
+            ]
+        });
+
+        self.query_database(&request_body).await
+    }
+
+    async fn query_punches(
+        &self,
+        filter: serde_json::Value,
+    ) -> Result<Vec<PunchRecord>, Box<dyn std::error::Error + Send + Sync>> {
         let request_body = serde_json::json!({
             "filter": filter,
             "sorts": [
@@ -125,13 +448,177 @@ impl NotionClient {
             ]
         });
 
+        let results = self.query_database(&request_body).await?;
+
+        Ok(results.iter().filter_map(parse_punch).collect())
+    }
+
+    /// 打刻時刻を修正し、修正前の時刻と理由をページに残す
+    pub async fn correct_record(
+        &self,
+        page_id: &str,
+        original: &chrono::DateTime<chrono::FixedOffset>,
+        corrected: &chrono::DateTime<chrono::FixedOffset>,
+        reason: &str,
+        corrected_at: &chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let request_body = NotionPageUpdateRequest {
+            properties: NotionCorrectionProperties {
+                timestamp: date_value(corrected),
+                correction_reason: rich_text(reason),
+                original_timestamp: rich_text(
+                    &original.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                ),
+                corrected_at: date_value(corrected_at),
+            },
+        };
+
+        self.update_page(page_id, &request_body).await
+    }
+
+    /// 打刻修正の申請を「承認待ち」で登録し、申請のページIDを返す
+    pub async fn create_correction_request(
+        &self,
+        request: &CorrectionRequest,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let properties = NotionProperties {
+            user_id: NotionTitle {
+                title: vec![NotionTextContent {
+                    text: NotionText {
+                        content: request.user_id.clone(),
+                    },
+                }],
+            },
+            user_name: rich_text(&request.user_name),
+            action: NotionSelect {
+                select: NotionOption {
+                    name: CORRECTION_REQUEST_LABEL.to_string(),
+                },
+            },
+            timestamp: date_value(&request.requested_at),
+            date: rich_text(&request.date),
+            correction_reason: Some(long_rich_text(&request.reason)),
+            granted_days: None,
+            location: None,
+            project: None,
+            note: None,
+            recorded_by: None,
+            approval_status: Some(NotionSelect {
+                select: NotionOption {
+                    name: request.status.label().to_string(),
+                },
+            }),
+            correction_detail: Some(long_rich_text(&serde_json::to_string(&request.detail)?)),
+        };
+
+        self.create_page(properties).await
+    }
+
+    /// ページIDから打刻修正の申請を取得する
+    pub async fn get_correction_request(
+        &self,
+        page_id: &str,
+    ) -> Result<CorrectionRequest, Box<dyn std::error::Error + Send + Sync>> {
         let response = self
             .client
-            .post(format!("https://api.notion.com/v1/databases/{}/query", self.database_id))
+            .get(format!("https://api.notion.com/v1/pages/{}", page_id))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Notion-Version", "2022-06-28")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(format!("Notion API error: {}", error_text).into());
+        }
+
+        let page: serde_json::Value = response.json().await?;
+        parse_correction_request(&page).ok_or_else(|| format!("Not a correction request: {}", page_id).into())
+    }
+
+    /// 指定期間（「日付」の前方一致）の打刻修正の申請を申請順に取得する。`user_id` が None なら全ユーザー分
+    pub async fn get_correction_requests(
+        &self,
+        user_id: Option<&str>,
+        period: &str,
+    ) -> Result<Vec<CorrectionRequest>, Box<dyn std::error::Error + Send + Sync>> {
+        let results = self
+            .query_by_actions(&[CORRECTION_REQUEST_LABEL], user_id, Some(period))
+            .await?;
+
+        Ok(results.iter().filter_map(parse_correction_request).collect())
+    }
+
+    /// 申請を「処理中」にして、処理を始めた承認者を記録する
+    pub async fn claim_correction_request(
+        &self,
+        page_id: &str,
+        approver_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let request_body = NotionPageUpdateRequest {
+            properties: NotionClaimProperties {
+                status: NotionSelect {
+                    select: NotionOption {
+                        name: ApprovalStatus::Processing.label().to_string(),
+                    },
+                },
+                approver: rich_text(approver_id),
+            },
+        };
+
+        self.update_page(page_id, &request_body).await
+    }
+
+    /// 承認の反映で追加した打刻のページIDを申請に記録する
+    pub async fn record_applied_pages(
+        &self,
+        page_id: &str,
+        applied_page_ids: &[String],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let request_body = NotionPageUpdateRequest {
+            properties: NotionAppliedPagesProperties {
+                applied_page_ids: long_rich_text(&applied_page_ids.join(",")),
+            },
+        };
+
+        self.update_page(page_id, &request_body).await
+    }
+
+    /// 申請の承認・却下を記録する
+    pub async fn decide_correction_request(
+        &self,
+        page_id: &str,
+        status: ApprovalStatus,
+        approver_id: &str,
+        decided_at: &chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let request_body = NotionPageUpdateRequest {
+            properties: NotionApprovalProperties {
+                status: NotionSelect {
+                    select: NotionOption {
+                        name: status.label().to_string(),
+                    },
+                },
+                approver: rich_text(approver_id),
+                decided_at: date_value(decided_at),
+            },
+        };
+
+        self.update_page(page_id, &request_body).await
+    }
+
+    async fn update_page<P: serde::Serialize>(
+        &self,
+        page_id: &str,
+        request_body: &NotionPageUpdateRequest<P>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let response = self
+            .client
+            .patch(format!("https://api.notion.com/v1/pages/{}", page_id))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Notion-Version", "2022-06-28")
             .header("Content-Type", "application/json")
-            .json(&request_body)
+            .json(request_body)
             .send()
             .await?;
 
@@ -140,133 +627,359 @@ impl NotionClient {
             return Err(format!("Notion API error: {}", error_text).into());
         }
 
-        let data: serde_json::Value = response.json().await?;
-        let results = data["results"].as_array().ok_or("No results found")?;
-        
-        // 日付ごとの勤務記録を格納
-        use std::collections::HashMap;
-        use chrono::{DateTime, FixedOffset, Utc};
-        
-        #[derive(Debug)]
-        struct DayRecord {
-            in_time: Option<DateTime<Utc>>,
-            out_time: Option<DateTime<Utc>>,
-            breaks: Vec<(DateTime<Utc>, Option<DateTime<Utc>>)>,
+        if let Some(page_log) = &self.page_log {
+            page_log.record(page_id);
         }
-        
-        let mut daily_records: HashMap<String, DayRecord> = HashMap::new();
-        
-        // レコードを日付ごとに整理
-        for result in results {
-            let properties = &result["properties"];
-            
-            // 日付を取得
-            let date = properties["日付"]["rich_text"][0]["text"]["content"]
-                .as_str()
-                .unwrap_or("")
-                .to_string();
-            
-            // タイムスタンプを取得
-            let timestamp_str = properties["タイムスタンプ"]["date"]["start"]
-                .as_str()
-                .unwrap_or("");
-            let timestamp = DateTime::parse_from_rfc3339(timestamp_str)
-                .map(|dt| dt.with_timezone(&Utc))
-                .ok();
-            
-            // アクションを取得
-            let action = properties["アクション"]["select"]["name"]
-                .as_str()
-                .unwrap_or("");
-            
-            if let Some(ts) = timestamp {
-                let record = daily_records.entry(date.clone()).or_insert(DayRecord {
-                    in_time: None,
-                    out_time: None,
-                    breaks: Vec::new(),
-                });
-                
-                match action {
-                    "出勤" => record.in_time = Some(ts),
-                    "退勤" => record.out_time = Some(ts),
-                    "休憩入り" => record.breaks.push((ts, None)),
-                    "休憩戻り" => {
-                        if let Some(last_break) = record.breaks.last_mut() {
-                            if last_break.1.is_none() {
-                                last_break.1 = Some(ts);
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+        Ok(())
+    }
+
+    /// 監査ログのデータベースに1件追加する（このクライアントは監査ログのデータベースIDで作成する）
+    pub async fn create_audit_entry(&self, entry: &AuditEntry) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let properties = NotionAuditProperties {
+            user_id: NotionTitle {
+                title: vec![NotionTextContent {
+                    text: NotionText {
+                        content: entry.user_id.clone(),
+                    },
+                }],
+            },
+            user_name: rich_text(&entry.user_name),
+            kind: NotionSelect {
+                select: NotionOption {
+                    name: entry.kind.clone(),
+                },
+            },
+            input: rich_text(&entry.input),
+            command: rich_text(&entry.command),
+            result: rich_text(entry.result.as_deref().unwrap_or_default()),
+            page_ids: rich_text(&entry.page_ids.join(",")),
+            error: rich_text(entry.error.as_deref().unwrap_or_default()),
+            timestamp: date_value(&entry.timestamp),
+            date: rich_text(&entry.timestamp.format("%Y-%m-%d").to_string()),
+        };
+
+        self.create_page(properties).await?;
+        Ok(())
+    }
+
+    /// 監査ログを新しい順に取得する。`period` は「日付」の前方一致（`2026-10-17` や `2026-10`）
+    pub async fn get_audit_entries(
+        &self,
+        user_id: Option<&str>,
+        period: &str,
+    ) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conditions = vec![serde_json::json!({
+            "property": "日付",
+            "rich_text": {
+                "starts_with": period
             }
+        })];
+        if let Some(user_id) = user_id {
+            conditions.push(serde_json::json!({
+                "property": "ユーザーID",
+                "title": {
+                    "equals": user_id
+                }
+            }));
         }
-        
-        // 勤務時間を計算
-        let mut total_work_minutes = 0i64;
-        let mut work_days = 0;
-        let mut report_lines = Vec::new();
-        
-        let mut dates: Vec<_> = daily_records.keys().collect();
-        dates.sort();
-        
-        for date in dates {
-            let record = &daily_records[date];
-            
-            if let (Some(in_time), Some(out_time)) = (record.in_time, record.out_time) {
-                work_days += 1;
-                
-                // 勤務時間を計算（分単位）
-                let work_duration = out_time - in_time;
-                let mut work_minutes = work_duration.num_minutes();
-                
-                // 休憩時間を引く
-                let mut break_minutes = 0i64;
-                for (break_start, break_end) in &record.breaks {
-                    if let Some(end) = break_end {
-                        let break_duration = *end - *break_start;
-                        break_minutes += break_duration.num_minutes();
-                    }
+
+        let request_body = serde_json::json!({
+            "filter": { "and": conditions },
+            "sorts": [
+                {
+                    "property": "日時",
+                    "direction": "descending"
                 }
-                
-                work_minutes -= break_minutes;
-                total_work_minutes += work_minutes;
-                
-                // 日本時間に変換して表示
-                let jst_offset = FixedOffset::east_opt(9 * 3600).unwrap();
-                let in_time_jst = in_time.with_timezone(&jst_offset);
-                let out_time_jst = out_time.with_timezone(&jst_offset);
-                
-                report_lines.push(format!(
-                    "{}  {:02}:{:02} - {:02}:{:02}  勤務: {}時間{}分  休憩: {}時間{}分",
-                    date,
-                    in_time_jst.hour(), in_time_jst.minute(),
-                    out_time_jst.hour(), out_time_jst.minute(),
-                    work_minutes / 60, work_minutes % 60,
-                    break_minutes / 60, break_minutes % 60
-                ));
+            ]
+        });
+
+        let results = self.query_database(&request_body).await?;
+
+        Ok(results.iter().filter_map(parse_audit_entry).collect())
+    }
+
+    /// データベースを検索し、全ページ分の結果を返す（Notionは1回100件までのため続きも取得する）
+    async fn query_database(
+        &self,
+        request_body: &serde_json::Value,
+    ) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut results = Vec::new();
+        let mut body = request_body.clone();
+
+        loop {
+            let response = self
+                .client
+                .post(format!("https://api.notion.com/v1/databases/{}/query", self.database_id))
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Notion-Version", "2022-06-28")
+                .header("Content-Type", "application/json")
+                .json(&body)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                return Err(format!("Notion API error: {}", error_text).into());
+            }
+
+            let data: serde_json::Value = response.json().await?;
+            let page = data["results"].as_array().ok_or("No results found")?;
+            results.extend(page.iter().cloned());
+
+            match data["next_cursor"].as_str() {
+                Some(cursor) if data["has_more"].as_bool().unwrap_or(false) => {
+                    body["start_cursor"] = serde_json::Value::from(cursor);
+                }
+                _ => break,
             }
         }
-        
-        // 合計時間を計算
-        let total_hours = total_work_minutes / 60;
-        let total_minutes = total_work_minutes % 60;
-        
-        let mut report = format!(
-            "{}年{}月の勤怠レポート\n\n",
-            year, month
-        );
-        
-        if report_lines.is_empty() {
-            report.push_str("勤務記録がありません");
-        } else {
-            report.push_str(&report_lines.join("\n"));
-            report.push_str(&format!(
-                "\n\n合計: {}日勤務  {}時間{}分",
-                work_days, total_hours, total_minutes
-            ));
+
+        Ok(results)
+    }
+}
+
+/// Notionのページを打刻に変換する。必要なプロパティが欠けているページは読み飛ばす
+fn parse_punch(result: &serde_json::Value) -> Option<PunchRecord> {
+    let properties = &result["properties"];
+    let action = AttendanceAction::from_label(properties["アクション"]["select"]["name"].as_str()?)?;
+    let timestamp =
+        chrono::DateTime::parse_from_rfc3339(properties["タイムスタンプ"]["date"]["start"].as_str()?).ok()?;
+
+    Some(PunchRecord {
+        page_id: result["id"].as_str()?.to_string(),
+        user_id: properties["ユーザーID"]["title"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        user_name: properties["ユーザー名"]["rich_text"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        date: properties["日付"]["rich_text"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        action,
+        timestamp,
+        location: properties["勤務場所"]["select"]["name"]
+            .as_str()
+            .and_then(WorkLocation::from_label),
+        note: plain_text(&properties["メモ"]),
+    })
+}
+
+/// Notionのページを休暇に変換する。打刻のページは読み飛ばす
+fn parse_leave(result: &serde_json::Value) -> Option<LeaveRecord> {
+    let properties = &result["properties"];
+    let leave_type = LeaveType::from_label(properties["アクション"]["select"]["name"].as_str()?)?;
+
+    Some(LeaveRecord {
+        page_id: result["id"].as_str()?.to_string(),
+        user_id: properties["ユーザーID"]["title"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        user_name: properties["ユーザー名"]["rich_text"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        date: properties["日付"]["rich_text"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        leave_type,
+    })
+}
+
+fn parse_task_switch(result: &serde_json::Value) -> Option<TaskSwitch> {
+    let properties = &result["properties"];
+
+    Some(TaskSwitch {
+        user_id: properties["ユーザーID"]["title"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        user_name: properties["ユーザー名"]["rich_text"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        date: properties["日付"]["rich_text"][0]["text"]["content"].as_str()?.to_string(),
+        timestamp: chrono::DateTime::parse_from_rfc3339(properties["タイムスタンプ"]["date"]["start"].as_str()?).ok()?,
+        project: properties["プロジェクト"]["rich_text"][0]["text"]["content"].as_str()?.to_string(),
+    })
+}
+
+fn parse_grant(result: &serde_json::Value) -> Option<LeaveGrant> {
+    let properties = &result["properties"];
+
+    Some(LeaveGrant {
+        user_id: properties["ユーザーID"]["title"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        user_name: properties["ユーザー名"]["rich_text"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        date: properties["日付"]["rich_text"][0]["text"]["content"].as_str()?.to_string(),
+        days: properties["付与日数"]["number"].as_f64()?,
+    })
+}
+
+fn parse_correction_request(result: &serde_json::Value) -> Option<CorrectionRequest> {
+    let properties = &result["properties"];
+    if properties["アクション"]["select"]["name"].as_str()? != CORRECTION_REQUEST_LABEL {
+        return None;
+    }
+
+    Some(CorrectionRequest {
+        page_id: result["id"].as_str()?.to_string(),
+        user_id: properties["ユーザーID"]["title"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        user_name: properties["ユーザー名"]["rich_text"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        date: properties["日付"]["rich_text"][0]["text"]["content"].as_str()?.to_string(),
+        reason: plain_text(&properties["修正理由"]).unwrap_or_default(),
+        requested_at: chrono::DateTime::parse_from_rfc3339(properties["タイムスタンプ"]["date"]["start"].as_str()?)
+            .ok()?,
+        status: ApprovalStatus::from_label(properties["承認状態"]["select"]["name"].as_str()?)?,
+        detail: serde_json::from_str(&plain_text(&properties["修正内容"])?).ok()?,
+        approver_id: plain_text(&properties["承認者"]),
+        applied_page_ids: plain_text(&properties["反映済みページ"])
+            .map(|ids| ids.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+    })
+}
+
+fn parse_audit_entry(result: &serde_json::Value) -> Option<AuditEntry> {
+    let properties = &result["properties"];
+
+    Some(AuditEntry {
+        timestamp: chrono::DateTime::parse_from_rfc3339(properties["日時"]["date"]["start"].as_str()?).ok()?,
+        user_id: properties["ユーザーID"]["title"][0]["text"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .to_string(),
+        user_name: plain_text(&properties["ユーザー名"]).unwrap_or_default(),
+        kind: properties["種類"]["select"]["name"].as_str().unwrap_or("").to_string(),
+        input: plain_text(&properties["入力"]).unwrap_or_default(),
+        command: plain_text(&properties["コマンド"]).unwrap_or_default(),
+        result: plain_text(&properties["結果"]),
+        page_ids: plain_text(&properties["ページID"])
+            .map(|ids| ids.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+        error: plain_text(&properties["エラー"]),
+    })
+}
+
+/// rich_text プロパティの全要素をつなげた文字列。空なら None
+fn plain_text(property: &serde_json::Value) -> Option<String> {
+    let text: String = property["rich_text"]
+        .as_array()?
+        .iter()
+        .filter_map(|t| t["plain_text"].as_str().or(t["text"]["content"].as_str()))
+        .collect();
+    Some(text).filter(|t| !t.is_empty())
+}
+
+fn rich_text(content: &str) -> NotionRichText {
+    NotionRichText {
+        rich_text: vec![NotionTextContent {
+            text: NotionText {
+                content: content.to_string(),
+            },
+        }],
+    }
+}
+
+/// 上限を超える文字列を複数の要素に分ける。読むときは `plain_text` がつなげる
+fn long_rich_text(content: &str) -> NotionRichText {
+    let mut chunks = vec![String::new()];
+    let mut length = 0;
+    for c in content.chars() {
+        if length + c.len_utf16() > MAX_TEXT_LENGTH {
+            chunks.push(String::new());
+            length = 0;
+        }
+        chunks.last_mut().unwrap().push(c);
+        length += c.len_utf16();
+    }
+
+    NotionRichText {
+        rich_text: chunks
+            .into_iter()
+            .map(|content| NotionTextContent {
+                text: NotionText { content },
+            })
+            .collect(),
+    }
+}
+
+fn date_value(timestamp: &chrono::DateTime<chrono::FixedOffset>) -> NotionDate {
+    NotionDate {
+        date: NotionDateValue {
+            start: timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_long_text_into_elements_within_the_limit() {
+        let detail = "修".repeat(4500);
+        let text = long_rich_text(&detail);
+
+        let lengths: Vec<usize> = text.rich_text.iter().map(|t| t.text.content.chars().count()).collect();
+        assert_eq!(lengths, [2000, 2000, 500]);
+
+        let property = serde_json::to_value(&text).unwrap();
+        assert_eq!(plain_text(&property), Some(detail));
+    }
+
+    #[test]
+    fn counts_characters_outside_the_bmp_as_two() {
+        let text = long_rich_text(&format!("{}🙂", "a".repeat(1999)));
+        assert_eq!(text.rich_text.len(), 2);
+        assert_eq!(text.rich_text[1].text.content, "🙂");
+    }
+
+    fn correction_request_page(applied_page_ids: Option<&str>) -> serde_json::Value {
+        let mut page = serde_json::json!({
+            "id": "request-1",
+            "properties": {
+                "アクション": { "select": { "name": CORRECTION_REQUEST_LABEL } },
+                "ユーザーID": { "title": [{ "text": { "content": "U1" } }] },
+                "ユーザー名": { "rich_text": [{ "text": { "content": "山田" } }] },
+                "日付": { "rich_text": [{ "text": { "content": "2026-10-16" } }] },
+                "修正理由": { "rich_text": [{ "plain_text": "打刻忘れ" }] },
+                "タイムスタンプ": { "date": { "start": "2026-10-16T19:00:00+09:00" } },
+                "承認状態": { "select": { "name": "処理中" } },
+                "修正内容": { "rich_text": [{ "plain_text": "{\"changes\":[],\"additions\":[]}" }] },
+                "承認者": { "rich_text": [{ "plain_text": "U2" }] },
+                "反映済みページ": { "rich_text": [] },
+            }
+        });
+        if let Some(ids) = applied_page_ids {
+            page["properties"]["反映済みページ"]["rich_text"] = serde_json::json!([{ "plain_text": ids }]);
         }
-        
-        Ok(report)
+        page
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reads_the_pages_applied_so_far() {
+        let request = parse_correction_request(&correction_request_page(Some("page-1,page-2"))).unwrap();
+        assert_eq!(request.status, ApprovalStatus::Processing);
+        assert_eq!(request.approver_id.as_deref(), Some("U2"));
+        assert_eq!(request.applied_page_ids, ["page-1", "page-2"]);
+
+        let request = parse_correction_request(&correction_request_page(None)).unwrap();
+        assert!(request.applied_page_ids.is_empty());
+    }
+}
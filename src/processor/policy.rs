@@ -0,0 +1,282 @@
+use crate::report::DaySummary;
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::Deserialize;
+
+/// 勤務時間の計算に適用する会社ルール
+#[derive(Debug, Clone, Default)]
+pub struct WorkPolicy {
+    pub breaks: BreakPolicy,
+    pub rounding: RoundingPolicy,
+}
+
+impl WorkPolicy {
+    /// 休憩の自動控除は実際の打刻で判定し、その後で端数処理する
+    pub fn apply(&self, days: &mut [DaySummary]) {
+        self.breaks.apply(days);
+        self.rounding.apply(days);
+    }
+}
+
+/// 休憩の打刻が足りない日に、勤務時間に応じた休憩を自動で控除するルール
+#[derive(Debug, Clone, Default)]
+pub struct BreakPolicy {
+    /// (勤務時間の閾値(分), 必要な休憩(分))。閾値を超えた勤務には必要な休憩を確保する
+    pub rules: Vec<(i64, i64)>,
+}
+
+impl BreakPolicy {
+    /// `BREAK_DEDUCTION_RULES` の形式 `勤務分:休憩分,...`（例: `360:45,480:60`）を読み込む
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let rules = text
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|rule| {
+                let (threshold, minutes) = rule
+                    .split_once(':')
+                    .ok_or_else(|| format!("BREAK_DEDUCTION_RULES: invalid rule `{}`", rule))?;
+                let parse = |v: &str| {
+                    v.trim()
+                        .parse::<i64>()
+                        .map_err(|_| format!("BREAK_DEDUCTION_RULES: invalid number in `{}`", rule))
+                };
+                Ok((parse(threshold)?, parse(minutes)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// 記録された休憩を除いた勤務時間に対して必要な休憩（分）
+    pub fn required_break_minutes(&self, worked_minutes: i64) -> i64 {
+        self.rules
+            .iter()
+            .filter(|(threshold, _)| worked_minutes > *threshold)
+            .map(|(_, minutes)| *minutes)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn apply(&self, days: &mut [DaySummary]) {
+        for day in days {
+            let (Some(in_time), Some(out_time)) = (day.in_time, day.out_time) else {
+                continue;
+            };
+            let recorded = day.break_minutes();
+            let worked = (out_time - in_time).num_minutes() - recorded;
+            day.auto_break_minutes = (self.required_break_minutes(worked) - recorded).max(0);
+        }
+    }
+}
+
+/// 端数処理の方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    #[default]
+    None,
+    Up,
+    Down,
+    Nearest,
+}
+
+/// 端数処理を打刻ごと（日単位）に行うか、月の合計に対して行うか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingScope {
+    #[default]
+    Day,
+    Month,
+}
+
+/// `ROUNDING_POLICY` の設定。例: `{"unit":15,"scope":"day","in":"up","out":"down"}`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RoundingPolicy {
+    /// 端数処理の単位（分）。0 または 1 なら端数処理しない
+    pub unit: i64,
+    pub scope: RoundingScope,
+    /// scope が day のときの打刻の種類ごとの方向
+    #[serde(rename = "in")]
+    pub clock_in: Rounding,
+    #[serde(rename = "out")]
+    pub clock_out: Rounding,
+    #[serde(rename = "break")]
+    pub break_start: Rounding,
+    #[serde(rename = "back")]
+    pub break_end: Rounding,
+    /// scope が month のときの月合計の方向
+    pub total: Rounding,
+}
+
+impl RoundingPolicy {
+    pub fn parse(json: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let policy: Self = serde_json::from_str(json)?;
+        if policy.unit < 0 || (policy.unit > 1 && 60 % policy.unit != 0) {
+            return Err(format!("ROUNDING_POLICY: unit must divide 60 minutes (got {})", policy.unit).into());
+        }
+        Ok(policy)
+    }
+
+    fn enabled(&self) -> bool {
+        self.unit > 1
+    }
+
+    /// 月の合計勤務時間（分）の端数処理。scope が day の場合は日ごとに処理済みなのでそのまま返す
+    pub fn round_total(&self, minutes: i64) -> i64 {
+        if self.enabled() && self.scope == RoundingScope::Month {
+            round_value(minutes, self.unit, self.total)
+        } else {
+            minutes
+        }
+    }
+
+    fn apply(&self, days: &mut [DaySummary]) {
+        if !self.enabled() || self.scope != RoundingScope::Day {
+            return;
+        }
+
+        let unit_seconds = self.unit * 60;
+        for day in days {
+            let (Some(in_time), Some(out_time)) = (day.in_time, day.out_time) else {
+                continue;
+            };
+            let start = round_time(in_time, unit_seconds, self.clock_in);
+            let end = round_time(out_time, unit_seconds, self.clock_out);
+            let breaks: i64 = day
+                .breaks
+                .iter()
+                .filter_map(|(break_start, break_end)| {
+                    let break_start = round_time(*break_start, unit_seconds, self.break_start);
+                    let break_end = round_time((*break_end)?, unit_seconds, self.break_end);
+                    Some((break_end - break_start).num_minutes().max(0))
+                })
+                .sum();
+
+            day.rounded_work_minutes =
+                Some(((end - start).num_minutes() - breaks - day.auto_break_minutes).max(0));
+        }
+    }
+}
+
+/// 日本時間の時刻を単位ごとに丸める
+fn round_time(time: DateTime<FixedOffset>, unit_seconds: i64, rounding: Rounding) -> DateTime<FixedOffset> {
+    let local_seconds = time.timestamp() + time.offset().local_minus_utc() as i64;
+    let rounded = round_value(local_seconds, unit_seconds, rounding);
+    time + Duration::seconds(rounded - local_seconds)
+}
+
+fn round_value(value: i64, unit: i64, rounding: Rounding) -> i64 {
+    let remainder = value.rem_euclid(unit);
+    match rounding {
+        Rounding::None => value,
+        Rounding::Down => value - remainder,
+        Rounding::Up if remainder == 0 => value,
+        Rounding::Up => value - remainder + unit,
+        Rounding::Nearest if remainder * 2 >= unit => value - remainder + unit,
+        Rounding::Nearest => value - remainder,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::summarize_days;
+    use crate::testing::punch;
+    use crate::types::{AttendanceAction, PunchRecord};
+
+    /// 2026-10-05 の (アクション, HH:MM) の並びを1日分の打刻にする
+    fn day(policy: &WorkPolicy, punches: &[(AttendanceAction, &str)]) -> DaySummary {
+        let punches: Vec<PunchRecord> = punches
+            .iter()
+            .map(|(action, time)| punch("2026-10-05", time, action.clone()))
+            .collect();
+        summarize_days(&punches, policy, None).remove(0)
+    }
+
+    #[test]
+    fn requires_the_break_for_the_longest_threshold_exceeded() {
+        let breaks = BreakPolicy::parse("360:45, 480:60").unwrap();
+        assert_eq!(breaks.required_break_minutes(360), 0);
+        assert_eq!(breaks.required_break_minutes(361), 45);
+        assert_eq!(breaks.required_break_minutes(480), 45);
+        assert_eq!(breaks.required_break_minutes(481), 60);
+        assert!(BreakPolicy::parse("480-60").is_err());
+    }
+
+    #[test]
+    fn deducts_only_the_missing_part_of_the_break() {
+        let policy = WorkPolicy {
+            breaks: BreakPolicy::parse("360:45,480:60").unwrap(),
+            rounding: RoundingPolicy::default(),
+        };
+
+        let no_break = day(&policy, &[(AttendanceAction::In, "09:00"), (AttendanceAction::Out, "18:00")]);
+        assert_eq!(no_break.auto_break_minutes, 60);
+        assert_eq!(no_break.work_minutes(), Some(480));
+
+        let short_break = day(
+            &policy,
+            &[
+                (AttendanceAction::In, "09:00"),
+                (AttendanceAction::Break, "12:00"),
+                (AttendanceAction::Back, "12:30"),
+                (AttendanceAction::Out, "18:00"),
+            ],
+        );
+        assert_eq!(short_break.auto_break_minutes, 30);
+        assert_eq!(short_break.work_minutes(), Some(480));
+
+        let enough_break = day(
+            &policy,
+            &[
+                (AttendanceAction::In, "09:00"),
+                (AttendanceAction::Break, "12:00"),
+                (AttendanceAction::Back, "13:00"),
+                (AttendanceAction::Out, "18:00"),
+            ],
+        );
+        assert_eq!(enough_break.auto_break_minutes, 0);
+    }
+
+    #[test]
+    fn rounds_values_to_the_unit() {
+        assert_eq!(round_value(7, 15, Rounding::Up), 15);
+        assert_eq!(round_value(15, 15, Rounding::Up), 15);
+        assert_eq!(round_value(14, 15, Rounding::Down), 0);
+        assert_eq!(round_value(7, 15, Rounding::Nearest), 0);
+        assert_eq!(round_value(8, 15, Rounding::Nearest), 15);
+        assert_eq!(round_value(7, 15, Rounding::None), 7);
+    }
+
+    #[test]
+    fn rounds_clock_in_up_and_clock_out_down() {
+        let policy = WorkPolicy {
+            breaks: BreakPolicy::default(),
+            rounding: RoundingPolicy::parse(r#"{"unit":15,"scope":"day","in":"up","out":"down"}"#).unwrap(),
+        };
+
+        let summary = day(&policy, &[(AttendanceAction::In, "08:52"), (AttendanceAction::Out, "18:07")]);
+        assert_eq!(summary.raw_work_minutes(), Some(555));
+        // 09:00〜18:00
+        assert_eq!(summary.work_minutes(), Some(540));
+
+        let on_the_unit = day(&policy, &[(AttendanceAction::In, "09:00"), (AttendanceAction::Out, "18:00")]);
+        assert_eq!(on_the_unit.work_minutes(), Some(540));
+    }
+
+    #[test]
+    fn rounds_the_monthly_total_only_in_month_scope() {
+        let month = RoundingPolicy::parse(r#"{"unit":30,"scope":"month","total":"down"}"#).unwrap();
+        assert_eq!(month.round_total(10_000), 9_990);
+
+        let daily = RoundingPolicy::parse(r#"{"unit":30,"scope":"day","in":"up"}"#).unwrap();
+        assert_eq!(daily.round_total(10_000), 10_000);
+    }
+
+    #[test]
+    fn rejects_units_that_do_not_divide_an_hour() {
+        assert!(RoundingPolicy::parse(r#"{"unit":7}"#).is_err());
+        assert!(RoundingPolicy::parse(r#"{"unit":15}"#).is_ok());
+    }
+}
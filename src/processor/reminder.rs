@@ -0,0 +1,118 @@
+use slack_attendance_processor::{config, jst, report, slack, types, workspace::Workspace};
+
+use aws_lambda_events::event::eventbridge::EventBridgeEvent;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use chrono::Timelike;
+use config::Config;
+use std::collections::BTreeMap;
+use report::WorkStatus;
+use types::*;
+
+/// Scheduled by EventBridge: DMs everyone who is still working (or on a break)
+/// after the configured hour, with a button to clock out.
+async fn function_handler(_event: LambdaEvent<EventBridgeEvent>) -> Result<(), Error> {
+    let mut config = Config::from_env()?;
+
+    let now = jst::now();
+    if now.hour() < config.reminder_hour {
+        tracing::info!("Before reminder hour ({}:00 JST), nothing to do", config.reminder_hour);
+        return Ok(());
+    }
+
+    let today = now.format("%Y-%m-%d").to_string();
+    config.workspaces.load_installations().await?;
+
+    // 同じデータベースを使うワークスペースは1回の検索で済ませる
+    let mut by_database: BTreeMap<String, Vec<Workspace>> = BTreeMap::new();
+    for workspace in config.workspaces.all() {
+        by_database.entry(workspace.notion_database_id.clone()).or_default().push(workspace);
+    }
+
+    for (database_id, workspaces) in by_database {
+        // 1つのデータベースの失敗で他のワークスペースへのリマインドを止めない
+        if let Err(e) = remind_database(&workspaces, &today).await {
+            tracing::warn!("Failed to send reminders for database `{}`: {}", database_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn remind_database(workspaces: &[Workspace], today: &str) -> Result<(), Error> {
+    let Some(first) = workspaces.first() else {
+        return Ok(());
+    };
+    let punches = first.notion_client()?.get_all_records_on(today).await?;
+
+    let still_working: Vec<(String, WorkStatus)> = report::group_by_user(&punches)
+        .into_iter()
+        .map(|(user_id, user_punches)| (user_id, report::current_status(&user_punches)))
+        .filter(|(_, status)| matches!(status, WorkStatus::Working { .. } | WorkStatus::OnBreak { .. }))
+        .collect();
+    if still_working.is_empty() {
+        return Ok(());
+    }
+
+    // データベースを共有していると他のワークスペースのユーザーも含まれるため、
+    // そのワークスペースのメンバーにだけ送る
+    let shared = workspaces.len() > 1;
+    for workspace in workspaces {
+        if let Err(e) = remind_workspace(workspace, &still_working, shared, today).await {
+            tracing::warn!("Failed to send reminders for workspace `{}`: {}", workspace.team_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn remind_workspace(
+    workspace: &Workspace,
+    still_working: &[(String, WorkStatus)],
+    shared: bool,
+    today: &str,
+) -> Result<(), Error> {
+    let slack_client = workspace.slack_client()?;
+    let members = if shared { Some(slack_client.member_ids().await?) } else { None };
+
+    for (user_id, status) in still_working {
+        if members.as_ref().is_some_and(|members| !members.contains(user_id)) {
+            continue;
+        }
+
+        let text = format!(
+            "まだ退勤が記録されていません（{}）。退勤する場合は下のボタンを押してください。",
+            status.describe()
+        );
+        let response = SlackResponse {
+            response_type: "ephemeral".to_string(),
+            text: text.clone(),
+            blocks: Some(vec![
+                Block::Section {
+                    text: slack::mrkdwn(&text),
+                },
+                Block::Actions {
+                    // 日付をまたいで押されてもこの日の退勤として記録する
+                    elements: vec![slack::reminder_out_button(today)],
+                },
+            ]),
+        };
+
+        // 1人への送信失敗で他のユーザーへのリマインドを止めない
+        if let Err(e) = slack_client.post_message(user_id, &response).await {
+            tracing::warn!("Failed to send clock-out reminder to {}: {}", user_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_target(false)
+        .without_time()
+        .init();
+
+    run(service_fn(function_handler)).await
+}
@@ -0,0 +1,560 @@
+use crate::calendar::{self, MonthAttendance};
+use crate::jst;
+use crate::config::Config;
+use crate::labor::{self, LaborSummary};
+use crate::policy::{RoundingPolicy, WorkPolicy};
+use crate::task;
+use crate::types::*;
+use chrono::{DateTime, FixedOffset, NaiveDate, Timelike};
+use std::collections::BTreeMap;
+
+/// 1日分の打刻を集計した結果
+#[derive(Debug, Clone)]
+pub struct DaySummary {
+    pub date: String,
+    pub in_time: Option<DateTime<FixedOffset>>,
+    pub out_time: Option<DateTime<FixedOffset>>,
+    pub breaks: Vec<(DateTime<FixedOffset>, Option<DateTime<FixedOffset>>)>,
+    /// 出勤時に指定された勤務場所
+    pub location: Option<WorkLocation>,
+    /// 打刻に添えられたメモ（「退勤: 早退: 通院」の形式）
+    pub notes: Vec<String>,
+    /// 休憩の記録が足りないため自動控除した休憩（分）
+    pub auto_break_minutes: i64,
+    /// 端数処理（日単位）を適用した実労働時間（分）。端数処理しない場合は None
+    pub rounded_work_minutes: Option<i64>,
+    /// 集計中に見つかった打刻の不整合（休憩戻りなしは1件ずつ記録する）
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl DaySummary {
+    fn new(date: &str) -> Self {
+        Self {
+            date: date.to_string(),
+            in_time: None,
+            out_time: None,
+            breaks: Vec::new(),
+            location: None,
+            notes: Vec::new(),
+            auto_break_minutes: 0,
+            rounded_work_minutes: None,
+            anomalies: Vec::new(),
+        }
+    }
+
+    /// 戻りが記録されていない休憩を `at` で終了させる
+    fn close_open_break(&mut self, at: DateTime<FixedOffset>) {
+        if let Some(last_break) = self.breaks.last_mut() {
+            if last_break.1.is_none() {
+                last_break.1 = Some(at.max(last_break.0));
+                self.anomalies.push(Anomaly::OpenBreak);
+            }
+        }
+    }
+
+    fn flag(&mut self, anomaly: Anomaly) {
+        if !self.anomalies.contains(&anomaly) {
+            self.anomalies.push(anomaly);
+        }
+    }
+
+    /// 休憩戻りが無く、次の打刻（休憩入り・退勤）の時刻で終了扱いにした休憩の数
+    pub fn open_breaks(&self) -> usize {
+        self.anomalies.iter().filter(|a| **a == Anomaly::OpenBreak).count()
+    }
+
+    /// 種類ごとに1件にまとめた不整合
+    pub fn distinct_anomalies(&self) -> Vec<&Anomaly> {
+        let mut distinct: Vec<&Anomaly> = Vec::new();
+        for anomaly in &self.anomalies {
+            if !distinct.contains(&anomaly) {
+                distinct.push(anomaly);
+            }
+        }
+        distinct
+    }
+
+    /// 出勤・退勤の欠けと勤務時間外の休憩を調べる。`today` の勤務中の日は退勤なしとしない
+    fn check_completeness(&mut self, today: Option<&str>) {
+        match (self.in_time, self.out_time) {
+            (Some(_), None) if today != Some(self.date.as_str()) => self.flag(Anomaly::MissingOut),
+            (None, Some(_)) => self.flag(Anomaly::MissingIn),
+            _ => {}
+        }
+
+        let outside = self.breaks.iter().any(|(start, end)| {
+            self.in_time.is_some_and(|in_time| *start < in_time)
+                || self
+                    .out_time
+                    .is_some_and(|out_time| *start > out_time || end.is_some_and(|end| end > out_time))
+        });
+        if outside {
+            self.flag(Anomaly::BreakOutsideWork);
+        }
+    }
+
+    /// 終了している休憩の合計（分）
+    pub fn break_minutes(&self) -> i64 {
+        self.breaks
+            .iter()
+            .filter_map(|(start, end)| end.map(|end| (end - *start).num_minutes()))
+            .sum()
+    }
+
+    /// 記録された休憩と自動控除を合わせた、勤務時間から差し引く休憩（分）
+    pub fn applied_break_minutes(&self) -> i64 {
+        self.break_minutes() + self.auto_break_minutes
+    }
+
+    /// 出勤・退勤が揃っている日の、端数処理前の実労働時間（分）
+    pub fn raw_work_minutes(&self) -> Option<i64> {
+        let (in_time, out_time) = (self.in_time?, self.out_time?);
+        Some((out_time - in_time).num_minutes() - self.applied_break_minutes())
+    }
+
+    /// 出勤・退勤が揃っている日の実労働時間（分）。端数処理の設定があれば処理後の値
+    pub fn work_minutes(&self) -> Option<i64> {
+        self.rounded_work_minutes.or_else(|| self.raw_work_minutes())
+    }
+
+    pub fn naive_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").ok()
+    }
+
+    /// 出勤から退勤までを記録された休憩で区切った勤務区間（自動控除分は時刻が無いため含まない）
+    pub fn work_sessions(&self) -> Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        let (Some(start), Some(end)) = (self.in_time, self.out_time) else {
+            return Vec::new();
+        };
+
+        let mut sessions = Vec::new();
+        let mut cursor = start;
+        for (break_start, break_end) in &self.breaks {
+            let Some(break_end) = break_end else { continue };
+            if *break_start > cursor && *break_start < end {
+                sessions.push((cursor, *break_start));
+            }
+            cursor = cursor.max(*break_end);
+        }
+        if cursor < end {
+            sessions.push((cursor, end));
+        }
+
+        sessions
+    }
+}
+
+/// 集計で見つかった打刻の不整合
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    MissingIn,
+    MissingOut,
+    DuplicateIn,
+    DuplicateOut,
+    OpenBreak,
+    BackWithoutBreak,
+    BreakOutsideWork,
+}
+
+impl Anomaly {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Anomaly::MissingIn => "出勤の打刻がありません",
+            Anomaly::MissingOut => "退勤の打刻がありません",
+            Anomaly::DuplicateIn => "出勤が複数回打刻されています（最後の出勤で計算）",
+            Anomaly::DuplicateOut => "退勤が複数回打刻されています（最後の退勤で計算）",
+            Anomaly::OpenBreak => "休憩戻りの打刻がありません（次の打刻の時刻で休憩終了として計算）",
+            Anomaly::BackWithoutBreak => "休憩入りのない休憩戻りがあります",
+            Anomaly::BreakOutsideWork => "勤務時間外に休憩が打刻されています",
+        }
+    }
+
+    /// 修正モーダルでの直し方
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Anomaly::MissingIn => "出勤時刻を追加してください",
+            Anomaly::MissingOut => "退勤時刻を追加してください",
+            Anomaly::DuplicateIn | Anomaly::DuplicateOut => "誤った打刻を正しい時刻に修正してください",
+            Anomaly::OpenBreak | Anomaly::BackWithoutBreak | Anomaly::BreakOutsideWork => {
+                "休憩の時刻を確認して修正してください"
+            }
+        }
+    }
+}
+
+/// チームレポート用のメンバーごとの月次集計
+#[derive(Debug, Clone)]
+pub struct MemberSummary {
+    pub user_id: String,
+    pub user_name: String,
+    pub work_days: usize,
+    /// 端数処理後の勤務時間（分）
+    pub work_minutes: i64,
+    /// 端数処理前の勤務時間（分）
+    pub raw_work_minutes: i64,
+    /// 時間外・深夜・休日労働の内訳
+    pub labor: LaborSummary,
+    /// 出勤・退勤のどちらかが欠けている日数
+    pub missing_punch_days: usize,
+    /// 休憩戻りが記録されていない休憩があった日数
+    pub open_break_days: usize,
+    /// 所定労働日との比較
+    pub attendance: MonthAttendance,
+    /// プロジェクト別の勤務時間（分）
+    pub projects: BTreeMap<String, i64>,
+}
+
+/// 現在の勤務状態
+#[derive(Debug, Clone)]
+pub enum WorkStatus {
+    NotStarted,
+    Working { since: DateTime<FixedOffset> },
+    OnBreak { since: DateTime<FixedOffset> },
+    Finished { at: DateTime<FixedOffset> },
+}
+
+impl WorkStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            WorkStatus::NotStarted => "未出勤".to_string(),
+            WorkStatus::Working { since } => format!("勤務中（{} から）", format_time(since)),
+            WorkStatus::OnBreak { since } => format!("休憩中（{} から）", format_time(since)),
+            WorkStatus::Finished { at } => format!("退勤済み（{} 退勤）", format_time(at)),
+        }
+    }
+}
+
+/// 打刻を日付ごとにまとめ、会社の計算ルールを適用する。打刻は時刻順に並んでいる前提。
+/// `today` の日はまだ勤務中の可能性があるため、退勤なしを不整合としない
+pub fn summarize_days(punches: &[PunchRecord], policy: &WorkPolicy, today: Option<&str>) -> Vec<DaySummary> {
+    let mut days: BTreeMap<&str, DaySummary> = BTreeMap::new();
+
+    for punch in punches {
+        let day = days
+            .entry(punch.date.as_str())
+            .or_insert_with(|| DaySummary::new(&punch.date));
+        if let Some(note) = &punch.note {
+            day.notes.push(format!("{}: {}", punch.action.label(), note));
+        }
+
+        match punch.action {
+            AttendanceAction::In => {
+                if day.in_time.is_some() {
+                    day.flag(Anomaly::DuplicateIn);
+                }
+                day.in_time = Some(punch.timestamp);
+                if punch.location.is_some() {
+                    day.location = punch.location.clone();
+                }
+            }
+            AttendanceAction::Out => {
+                if day.out_time.is_some() {
+                    day.flag(Anomaly::DuplicateOut);
+                }
+                day.close_open_break(punch.timestamp);
+                day.out_time = Some(punch.timestamp);
+            }
+            AttendanceAction::Break => {
+                day.close_open_break(punch.timestamp);
+                day.breaks.push((punch.timestamp, None));
+            }
+            AttendanceAction::Back => match day.breaks.last_mut() {
+                Some(last_break) if last_break.1.is_none() => last_break.1 = Some(punch.timestamp),
+                _ => day.flag(Anomaly::BackWithoutBreak),
+            },
+        }
+    }
+
+    let mut days: Vec<DaySummary> = days.into_values().collect();
+    for day in &mut days {
+        day.check_completeness(today);
+    }
+    policy.apply(&mut days);
+    days
+}
+
+/// 当日の打刻から現在の状態を判定する
+pub fn current_status(today: &[PunchRecord]) -> WorkStatus {
+    match today.last() {
+        None => WorkStatus::NotStarted,
+        Some(last) => match last.action {
+            AttendanceAction::In | AttendanceAction::Back => WorkStatus::Working { since: last.timestamp },
+            AttendanceAction::Break => WorkStatus::OnBreak { since: last.timestamp },
+            AttendanceAction::Out => WorkStatus::Finished { at: last.timestamp },
+        },
+    }
+}
+
+pub fn format_monthly_report(
+    year: i32,
+    month: u32,
+    days: &[DaySummary],
+    labor: &LaborSummary,
+    rounding: &RoundingPolicy,
+    attendance: &MonthAttendance,
+    projects: &BTreeMap<String, i64>,
+) -> String {
+    let mut total_work_minutes = 0i64;
+    let mut raw_work_minutes = 0i64;
+    let mut work_days = 0;
+    let mut auto_break_days = 0;
+    let mut report_lines = Vec::new();
+
+    for day in days {
+        let (Some(in_time), Some(out_time), Some(work_minutes)) = (day.in_time, day.out_time, day.work_minutes())
+        else {
+            continue;
+        };
+
+        work_days += 1;
+        total_work_minutes += work_minutes;
+        let raw_minutes = day.raw_work_minutes().unwrap_or(work_minutes);
+        raw_work_minutes += raw_minutes;
+        let break_minutes = day.break_minutes();
+
+        let mut line = format!(
+            "{}  {} - {}  勤務: {}時間{}分",
+            day.date,
+            format_time(&in_time),
+            format_time(&out_time),
+            work_minutes / 60, work_minutes % 60
+        );
+        if raw_minutes != work_minutes {
+            line.push_str(&format!("（端数処理前 {}）", format_duration(raw_minutes)));
+        }
+        line.push_str(&format!("  休憩: {}", format_duration(break_minutes)));
+        if day.auto_break_minutes > 0 {
+            auto_break_days += 1;
+            line.push_str(&format!(
+                "（記録 {} / 適用 {}）",
+                format_duration(break_minutes),
+                format_duration(day.applied_break_minutes())
+            ));
+        }
+        if let Some((_, reason)) = attendance.holiday_work.iter().find(|(date, _)| Some(*date) == day.naive_date()) {
+            line.push_str(&format!("  ［休日出勤: {}］", reason));
+        }
+        if let Some(leave_type) = day.naive_date().and_then(|date| attendance.leave_on(date)) {
+            line.push_str(&format!("  ［{}］", leave_type.label()));
+        }
+        if !day.anomalies.is_empty() {
+            line.push_str("  ⚠️");
+        }
+        if !day.notes.is_empty() {
+            line.push_str(&format!("  📝 {}", day.notes.join(" / ")));
+        }
+        report_lines.push(line);
+    }
+
+    let mut report = format!("{}年{}月の勤怠レポート\n\n", year, month);
+
+    if report_lines.is_empty() {
+        report.push_str("勤務記録がありません");
+        if !attendance.leaves.is_empty() {
+            report.push_str(&format!("\n\n{}", attendance.format()));
+        }
+    } else {
+        report.push_str(&report_lines.join("\n"));
+        let total_work_minutes = rounding.round_total(total_work_minutes);
+        report.push_str(&format!(
+            "\n\n合計: {}日勤務  {}",
+            work_days,
+            format_duration(total_work_minutes)
+        ));
+        if raw_work_minutes != total_work_minutes {
+            report.push_str(&format!("（端数処理前 {}）", format_duration(raw_work_minutes)));
+        }
+        report.push_str(&format!("\n{}", attendance.format()));
+        if let Some(locations) = format_locations(days) {
+            report.push_str(&format!("\n{}", locations));
+        }
+        if auto_break_days > 0 {
+            report.push_str(&format!("\n休憩の自動控除: {}日", auto_break_days));
+        }
+        report.push_str(&format!("\n{}", labor.format()));
+        if let Some(section) = task::format_allocation(projects) {
+            report.push_str(&format!("\n\n{}", section));
+        }
+    }
+
+    if let Some(section) = format_anomalies(days) {
+        report.push_str(&format!("\n\n{}", section));
+    }
+
+    report
+}
+
+/// 勤務場所ごとの出勤日数。勤務場所の記録が1日も無ければ None
+pub fn format_locations(days: &[DaySummary]) -> Option<String> {
+    let mut counts: BTreeMap<Option<&WorkLocation>, usize> = BTreeMap::new();
+    for day in days.iter().filter(|d| d.in_time.is_some()) {
+        *counts.entry(day.location.as_ref()).or_default() += 1;
+    }
+    if counts.keys().all(|location| location.is_none()) {
+        return None;
+    }
+
+    let parts: Vec<String> = counts
+        .iter()
+        .map(|(location, count)| {
+            let label = location.map(|l| l.label()).unwrap_or_else(|| "未指定".to_string());
+            format!("{} {}日", label, count)
+        })
+        .collect();
+    Some(format!("勤務場所: {}", parts.join("、")))
+}
+
+/// 不整合のある日の一覧と修正方法。不整合が無ければ None
+pub fn format_anomalies(days: &[DaySummary]) -> Option<String> {
+    let flagged: Vec<&DaySummary> = days.iter().filter(|d| !d.anomalies.is_empty()).collect();
+    if flagged.is_empty() {
+        return None;
+    }
+
+    let lines: Vec<String> = flagged
+        .iter()
+        .flat_map(|day| {
+            day.distinct_anomalies().into_iter().map(move |anomaly| {
+                format!(
+                    "・{}  {} → {}: `/attendance fix {}`",
+                    day.date,
+                    anomaly.describe(),
+                    anomaly.hint(),
+                    day.date
+                )
+            })
+        })
+        .collect();
+
+    Some(format!("⚠️ 要確認の日（{}日）\n{}", flagged.len(), lines.join("\n")))
+}
+
+/// 全員分の打刻をユーザーIDごとに分ける（時刻順は保たれる）
+pub fn group_by_user(punches: &[PunchRecord]) -> BTreeMap<String, Vec<PunchRecord>> {
+    let mut by_user: BTreeMap<String, Vec<PunchRecord>> = BTreeMap::new();
+    for punch in punches {
+        by_user.entry(punch.user_id.clone()).or_default().push(punch.clone());
+    }
+    by_user
+}
+
+/// 全員分の打刻と休暇をメンバーごとに集計する（休暇だけのメンバーも含む）。
+/// `punches` には月初を含む週の前月分（`labor::prior_week_dates`）を含めてよく、週40時間の判定にだけ使う
+pub fn summarize_members(
+    config: &Config,
+    year: i32,
+    month: u32,
+    punches: &[PunchRecord],
+    leaves: &[LeaveRecord],
+    switches: &[TaskSwitch],
+    today: &str,
+) -> Vec<MemberSummary> {
+    let policy = &config.work_policy;
+    let period = format!("{}-{:02}", year, month);
+    let (punches, prior_punches): (Vec<PunchRecord>, Vec<PunchRecord>) =
+        punches.iter().cloned().partition(|p| p.date.starts_with(&period));
+    let prior_by_user = group_by_user(&prior_punches);
+    let mut by_user = group_by_user(&punches);
+    for leave in leaves {
+        by_user.entry(leave.user_id.clone()).or_default();
+    }
+
+    by_user
+        .into_iter()
+        .map(|(user_id, user_punches)| {
+            let days = summarize_days(&user_punches, policy, Some(today));
+            let prior_days = prior_by_user
+                .get(&user_id)
+                .map(|p| summarize_days(p, policy, None))
+                .unwrap_or_default();
+            let worked: Vec<i64> = days.iter().filter_map(|d| d.work_minutes()).collect();
+            let user_leaves: Vec<LeaveRecord> = leaves.iter().filter(|l| l.user_id == user_id).cloned().collect();
+            let user_switches: Vec<TaskSwitch> = switches.iter().filter(|s| s.user_id == user_id).cloned().collect();
+
+            MemberSummary {
+                user_name: user_punches
+                    .last()
+                    .map(|p| p.user_name.clone())
+                    .or_else(|| user_leaves.last().map(|l| l.user_name.clone()))
+                    .unwrap_or_default(),
+                user_id,
+                work_days: worked.len(),
+                work_minutes: policy.rounding.round_total(worked.iter().sum()),
+                raw_work_minutes: days.iter().filter_map(|d| d.raw_work_minutes()).sum(),
+                labor: labor::evaluate(&config.labor_rules, &days, &prior_days),
+                missing_punch_days: days
+                    .iter()
+                    .filter(|d| d.anomalies.iter().any(|a| matches!(a, Anomaly::MissingIn | Anomaly::MissingOut)))
+                    .count(),
+                open_break_days: days.iter().filter(|d| d.open_breaks() > 0).count(),
+                attendance: calendar::month_attendance(&config.calendar, year, month, &days, &user_leaves, today),
+                projects: task::allocate(&days, &user_switches),
+            }
+        })
+        .collect()
+}
+
+pub fn format_team_report(year: i32, month: u32, members: &[MemberSummary]) -> String {
+    let mut report = format!("{}年{}月のチーム勤怠レポート\n\n", year, month);
+    if let Some(member) = members.first() {
+        report.push_str(&format!("所定労働日: {}日\n\n", member.attendance.scheduled_days));
+    }
+
+    if members.is_empty() {
+        report.push_str("勤務記録がありません");
+        return report;
+    }
+
+    let lines: Vec<String> = members
+        .iter()
+        .map(|m| {
+            format!(
+                "{}{} (<@{}>)  {}日  勤務: {}{}  残業: {}  深夜: {}  休日: {}  休日出勤: {}日  欠勤: {}日  有給: {}日  打刻漏れ: {}日  休憩戻り漏れ: {}日",
+                if m.labor.breaches.is_empty() { "" } else { "⚠️ " },
+                m.user_name,
+                m.user_id,
+                m.work_days,
+                format_duration(m.work_minutes),
+                if m.raw_work_minutes == m.work_minutes {
+                    String::new()
+                } else {
+                    format!("（端数処理前 {}）", format_duration(m.raw_work_minutes))
+                },
+                format_duration(m.labor.statutory_overtime_minutes),
+                format_duration(m.labor.late_night_minutes),
+                format_duration(m.labor.holiday_minutes),
+                m.attendance.holiday_work.len(),
+                m.attendance.absences.len(),
+                m.attendance.paid_leave_days(),
+                m.missing_punch_days,
+                m.open_break_days
+            )
+        })
+        .collect();
+    report.push_str(&lines.join("\n"));
+
+    let mut projects: BTreeMap<String, i64> = BTreeMap::new();
+    for (project, minutes) in members.iter().flat_map(|m| &m.projects) {
+        *projects.entry(project.clone()).or_default() += minutes;
+    }
+    if let Some(section) = task::format_allocation(&projects) {
+        report.push_str(&format!("\n\n{}", section));
+    }
+
+    let breached = members.iter().filter(|m| !m.labor.breaches.is_empty()).count();
+    if breached > 0 {
+        report.push_str(&format!("\n\n⚠️ 36協定の上限を超えているメンバー: {}人", breached));
+    }
+
+    report
+}
+
+pub fn format_duration(minutes: i64) -> String {
+    format!("{}時間{}分", minutes / 60, minutes % 60)
+}
+
+/// 日本時間の HH:MM で表示する
+pub fn format_time(timestamp: &DateTime<FixedOffset>) -> String {
+    let jst = timestamp.with_timezone(&jst::offset());
+    format!("{:02}:{:02}", jst.hour(), jst.minute())
+}
@@ -1,42 +1,622 @@
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
-use hex;
-use std::time::{SystemTime, UNIX_EPOCH};
-
-type HmacSha256 = Hmac<Sha256>;
-
-pub fn verify_slack_signature(
-    signing_secret: &str,
-    body: &str,
-    timestamp: &str,
-    signature: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    let current_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)?
-        .as_secs();
-    
-    let request_timestamp: u64 = timestamp.parse()?;
-    
-    if current_time.abs_diff(request_timestamp) > 60 * 5 {
-        return Ok(false);
-    }
-    
-    let base_string = format!("v0:{}:{}", timestamp, body);
-    
-    let mut mac = HmacSha256::new_from_slice(signing_secret.as_bytes())?;
-    mac.update(base_string.as_bytes());
-    let result = mac.finalize();
-    let calculated_signature = format!("v0={}", hex::encode(result.into_bytes()));
-    
-    Ok(calculated_signature == signature)
-}
-
-pub fn parse_command_text(text: &str) -> Result<crate::types::AttendanceAction, String> {
+use crate::export::ExportFormat;
+use crate::types::*;
+use reqwest::Client;
+use std::collections::HashSet;
+
+/// Minimal Slack Web API client for messages that have no `response_url` to reply to.
+pub struct SlackClient {
+    client: Client,
+    bot_token: String,
+}
+
+impl SlackClient {
+    pub fn new(bot_token: String) -> Self {
+        Self {
+            client: Client::new(),
+            bot_token,
+        }
+    }
+
+    pub async fn post_message(
+        &self,
+        channel: &str,
+        response: &SlackResponse,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut body = serde_json::json!({
+            "channel": channel,
+            "text": response.text,
+        });
+        if let Some(blocks) = &response.blocks {
+            body["blocks"] = serde_json::to_value(blocks)?;
+        }
+
+        self.call("chat.postMessage", &body).await?;
+        Ok(())
+    }
+
+    pub async fn post_ephemeral(
+        &self,
+        channel: &str,
+        user: &str,
+        response: &SlackResponse,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut body = serde_json::json!({
+            "channel": channel,
+            "user": user,
+            "text": response.text,
+        });
+        if let Some(blocks) = &response.blocks {
+            body["blocks"] = serde_json::to_value(blocks)?;
+        }
+
+        self.call("chat.postEphemeral", &body).await?;
+        Ok(())
+    }
+
+    /// Events don't carry the user name, so look it up with users.info.
+    pub async fn get_user_name(
+        &self,
+        user_id: &str,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let body = serde_json::json!({ "user": user_id });
+        let data = self.call_form("users.info", &body).await?;
+
+        Ok(data["user"]["name"].as_str().unwrap_or(user_id).to_string())
+    }
+
+    /// IDs of every member of the bot's workspace (users.list, following the cursor).
+    pub async fn member_ids(&self) -> Result<HashSet<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut members = HashSet::new();
+        let mut cursor = String::new();
+
+        loop {
+            let body = serde_json::json!({ "limit": "200", "cursor": cursor });
+            let data = self.call_form("users.list", &body).await?;
+
+            if let Some(users) = data["members"].as_array() {
+                members.extend(users.iter().filter_map(|user| user["id"].as_str().map(str::to_string)));
+            }
+
+            cursor = data["response_metadata"]["next_cursor"].as_str().unwrap_or_default().to_string();
+            if cursor.is_empty() {
+                break;
+            }
+        }
+
+        Ok(members)
+    }
+
+    pub async fn update_view(
+        &self,
+        view_id: &str,
+        view: &SlackView,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let body = serde_json::json!({
+            "view_id": view_id,
+            "view": view,
+        });
+
+        self.call("views.update", &body).await?;
+        Ok(())
+    }
+
+    pub async fn publish_view(
+        &self,
+        user_id: &str,
+        view: &SlackView,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let body = serde_json::json!({
+            "user_id": user_id,
+            "view": view,
+        });
+
+        self.call("views.publish", &body).await?;
+        Ok(())
+    }
+
+    /// Uploads a file into the user's DM with the bot using the files.uploadV2 flow
+    /// (getUploadURLExternal → upload → completeUploadExternal).
+    pub async fn upload_file_to_dm(
+        &self,
+        user_id: &str,
+        filename: &str,
+        content: Vec<u8>,
+        initial_comment: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let dm = self.call("conversations.open", &serde_json::json!({ "users": user_id })).await?;
+        let channel_id = dm["channel"]["id"].as_str().ok_or("conversations.open returned no channel")?;
+
+        let upload = self
+            .call_form(
+                "files.getUploadURLExternal",
+                &serde_json::json!({ "filename": filename, "length": content.len() }),
+            )
+            .await?;
+        let upload_url = upload["upload_url"].as_str().ok_or("No upload_url in response")?;
+        let file_id = upload["file_id"].as_str().ok_or("No file_id in response")?;
+
+        self.client
+            .post(upload_url)
+            .body(content)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let body = serde_json::json!({
+            "files": [{ "id": file_id, "title": filename }],
+            "channel_id": channel_id,
+            "initial_comment": initial_comment,
+        });
+        self.call("files.completeUploadExternal", &body).await?;
+
+        Ok(())
+    }
+
+    /// Some read methods (e.g. users.info) only accept form-encoded arguments.
+    async fn call_form(
+        &self,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self
+            .client
+            .post(format!("https://slack.com/api/{}", method))
+            .header("Authorization", format!("Bearer {}", self.bot_token))
+            .form(params)
+            .send()
+            .await?;
+
+        check_ok(method, response.json().await?)
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self
+            .client
+            .post(format!("https://slack.com/api/{}", method))
+            .header("Authorization", format!("Bearer {}", self.bot_token))
+            .json(body)
+            .send()
+            .await?;
+
+        check_ok(method, response.json().await?)
+    }
+}
+
+/// Slack returns 200 even on failure and reports errors through the `ok` flag.
+fn check_ok(
+    method: &str,
+    data: serde_json::Value,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    if !data["ok"].as_bool().unwrap_or(false) {
+        return Err(format!("Slack API error ({}): {}", method, data["error"]).into());
+    }
+
+    Ok(data)
+}
+
+/// 打刻の指定（`in remote` など）
+#[derive(Debug, Clone)]
+pub struct PunchRequest {
+    pub action: AttendanceAction,
+    pub location: Option<WorkLocation>,
+    /// 打刻に添えるメモ（`out 早退: 通院` の「早退: 通院」）
+    pub note: Option<String>,
+    /// 打刻を記録する勤務日。省略時は打刻した日（日付をまたいで押された退勤リマインドのボタン用）
+    pub work_date: Option<chrono::NaiveDate>,
+}
+
+impl From<AttendanceAction> for PunchRequest {
+    fn from(action: AttendanceAction) -> Self {
+        Self {
+            action,
+            location: None,
+            note: None,
+            work_date: None,
+        }
+    }
+}
+
+/// `/attendance` のサブコマンド
+#[derive(Debug, Clone)]
+pub enum Command {
+    Punch(PunchRequest),
+    Report {
+        scope: ReportScope,
+        /// (年, 月)。省略時は今月
+        month: Option<(i32, u32)>,
+    },
+    Status,
+    Export {
+        scope: ReportScope,
+        /// 指定したユーザーの分（管理者のみ）
+        user_id: Option<String>,
+        month: Option<(i32, u32)>,
+        format: ExportFormat,
+    },
+    Leave {
+        date: chrono::NaiveDate,
+        leave_type: LeaveType,
+    },
+    LeaveBalance,
+    /// 作業中のプロジェクトを切り替える
+    Task {
+        project: String,
+    },
+    /// 管理者による有給休暇の付与
+    LeaveGrant {
+        user_id: String,
+        date: chrono::NaiveDate,
+        days: f64,
+    },
+    /// 管理者による他のユーザーの代理打刻
+    AdminPunch {
+        user_id: String,
+        action: AttendanceAction,
+        time: chrono::NaiveTime,
+        /// 省略時は今日
+        date: Option<chrono::NaiveDate>,
+        reason: Option<String>,
+    },
+    /// 管理者による監査ログの検索
+    Audit {
+        user_id: Option<String>,
+        /// `YYYY-MM-DD` または `YYYY-MM`。省略時は今日
+        period: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportScope {
+    Own,
+    Team,
+}
+
+pub fn parse_command(text: &str) -> Result<Command, String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+
+    match words.first().map(|w| w.to_lowercase()).as_deref() {
+        Some("report") => parse_report(&words[1..]),
+        Some("status") => Ok(Command::Status),
+        Some("export") => parse_export(&words[1..]),
+        Some("leave") => parse_leave(&words[1..]),
+        Some("admin") => parse_admin(&words[1..]),
+        Some("audit") => parse_audit(&words[1..]),
+        Some("task") => match &words[1..] {
+            [project] => Ok(Command::Task {
+                project: project.to_string(),
+            }),
+            _ => Err("Usage: task <PROJECT>（例: task PROJ-123）".to_string()),
+        },
+        _ => parse_punch(text).map(Command::Punch),
+    }
+}
+
+/// `<in|break|back|out> [メモ]`。`in` は直後に勤務場所（remote / office / client:NAME）を指定できる。
+/// 例: `in remote`、`in client:ACME 直行`、`out 早退: 通院`
+fn parse_punch(text: &str) -> Result<PunchRequest, String> {
+    let text = text.trim();
+    let (keyword, mut rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let action = parse_command_text(keyword)?;
+
+    let mut location = None;
+    if action == AttendanceAction::In {
+        let (first, after) = rest.trim_start().split_once(char::is_whitespace).unwrap_or((rest.trim_start(), ""));
+        if let Some(parsed) = WorkLocation::parse(first) {
+            location = Some(parsed);
+            rest = after;
+        }
+    }
+
+    let note = Some(rest.trim()).filter(|n| !n.is_empty()).map(str::to_string);
+
+    Ok(PunchRequest {
+        action,
+        location,
+        note,
+        work_date: None,
+    })
+}
+
+/// `report [team] [YYYY-MM]`
+fn parse_report(args: &[&str]) -> Result<Command, String> {
+    let (scope, rest) = match args.first() {
+        Some(&"team") => (ReportScope::Team, &args[1..]),
+        _ => (ReportScope::Own, args),
+    };
+
+    let month = match rest {
+        [] => None,
+        [month] => Some(parse_month(month)?),
+        _ => return Err("Usage: report [team] [YYYY-MM]".to_string()),
+    };
+
+    Ok(Command::Report { scope, month })
+}
+
+/// `export [team|@user] [YYYY-MM] [csv|xlsx]`（順不同）
+fn parse_export(args: &[&str]) -> Result<Command, String> {
+    let mut scope = ReportScope::Own;
+    let mut user_id = None;
+    let mut month = None;
+    let mut format = ExportFormat::Csv;
+
+    for arg in args {
+        match arg.to_lowercase().as_str() {
+            "team" => scope = ReportScope::Team,
+            "csv" => format = ExportFormat::Csv,
+            "xlsx" | "excel" => format = ExportFormat::Xlsx,
+            _ => match parse_user_mention(arg) {
+                Some(id) => user_id = Some(id),
+                None => month = Some(parse_month(arg)?),
+            },
+        }
+    }
+
+    if scope == ReportScope::Team && user_id.is_some() {
+        return Err("Usage: export [team|@user] [YYYY-MM] [csv|xlsx]".to_string());
+    }
+
+    Ok(Command::Export {
+        scope,
+        user_id,
+        month,
+        format,
+    })
+}
+
+/// `leave YYYY-MM-DD <種類>` / `leave balance` / `leave grant @user YYYY-MM-DD <日数>`
+fn parse_leave(args: &[&str]) -> Result<Command, String> {
+    match args {
+        ["balance"] => return Ok(Command::LeaveBalance),
+        ["grant", user, date, days] => {
+            let user_id =
+                parse_user_mention(user).ok_or_else(|| format!("ユーザーの指定が正しくありません: {}", user))?;
+            let days: f64 = days
+                .parse()
+                .ok()
+                .filter(|d: &f64| *d > 0.0 && (d * 2.0).fract() == 0.0)
+                .ok_or_else(|| format!("付与日数が正しくありません: {}。0.5日単位で指定してください", days))?;
+            return Ok(Command::LeaveGrant {
+                user_id,
+                date: parse_date(date)?,
+                days,
+            });
+        }
+        ["grant", ..] => return Err("Usage: leave grant @user YYYY-MM-DD <days>".to_string()),
+        _ => {}
+    }
+
+    let [date, leave_type] = args else {
+        return Err(
+            "Usage: leave YYYY-MM-DD paid|half-am|half-pm|sick|special|absence / leave balance".to_string(),
+        );
+    };
+
+    let date = parse_date(date)?;
+    let leave_type = LeaveType::parse(leave_type).ok_or_else(|| {
+        format!(
+            "休暇の種類が正しくありません: {}。paid, half-am, half-pm, sick, special, absence のいずれかを指定してください",
+            leave_type
+        )
+    })?;
+
+    Ok(Command::Leave { date, leave_type })
+}
+
+/// `admin <in|break|back|out> @user HH:MM [YYYY-MM-DD] [理由]`
+fn parse_admin(args: &[&str]) -> Result<Command, String> {
+    let [action, user, time, rest @ ..] = args else {
+        return Err("Usage: admin <in|break|back|out> @user HH:MM [YYYY-MM-DD] [理由]".to_string());
+    };
+
+    let action = parse_command_text(action)?;
+    let user_id = parse_user_mention(user).ok_or_else(|| format!("ユーザーの指定が正しくありません: {}", user))?;
+    let time = chrono::NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("時刻の形式が正しくありません: {}。例: 09:00", time))?;
+
+    let (date, rest) = match rest.split_first() {
+        Some((first, after)) if chrono::NaiveDate::parse_from_str(first, "%Y-%m-%d").is_ok() => {
+            (Some(parse_date(first)?), after)
+        }
+        _ => (None, rest),
+    };
+    let reason = Some(rest.join(" ")).filter(|r| !r.is_empty());
+
+    Ok(Command::AdminPunch {
+        user_id,
+        action,
+        time,
+        date,
+        reason,
+    })
+}
+
+/// `audit [@user] [YYYY-MM-DD|YYYY-MM]`（順不同）
+fn parse_audit(args: &[&str]) -> Result<Command, String> {
+    let mut user_id = None;
+    let mut period = None;
+
+    for arg in args {
+        if let Some(id) = parse_user_mention(arg) {
+            user_id = Some(id);
+        } else if parse_date(arg).is_ok() || parse_month(arg).is_ok() {
+            period = Some(arg.to_string());
+        } else {
+            return Err("Usage: audit [@user] [YYYY-MM-DD|YYYY-MM]".to_string());
+        }
+    }
+
+    Ok(Command::Audit { user_id, period })
+}
+
+fn parse_date(text: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("日付の形式が正しくありません: {}。例: 2026-10-20", text))
+}
+
+/// Slackのメンション（`<@U123|name>` / `<@U123>`）またはユーザーIDからユーザーIDを取り出す
+pub fn parse_user_mention(text: &str) -> Option<String> {
+    let id = match text.strip_prefix("<@").and_then(|t| t.strip_suffix('>')) {
+        Some(inner) => inner.split('|').next()?,
+        None => text,
+    };
+
+    let valid = id.len() > 1
+        && (id.starts_with('U') || id.starts_with('W'))
+        && id.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    valid.then(|| id.to_string())
+}
+
+pub fn parse_month(text: &str) -> Result<(i32, u32), String> {
+    chrono::NaiveDate::parse_from_str(&format!("{}-01", text), "%Y-%m-%d")
+        .map(|d| {
+            use chrono::Datelike;
+            (d.year(), d.month())
+        })
+        .map_err(|_| format!("月の形式が正しくありません: {}。例: 2026-09", text))
+}
+
+pub fn parse_command_text(text: &str) -> Result<AttendanceAction, String> {
     match text.trim().to_lowercase().as_str() {
-        "in" => Ok(crate::types::AttendanceAction::In),
-        "break" => Ok(crate::types::AttendanceAction::Break),
-        "back" => Ok(crate::types::AttendanceAction::Back),
-        "out" => Ok(crate::types::AttendanceAction::Out),
+        "in" => Ok(AttendanceAction::In),
+        "break" => Ok(AttendanceAction::Break),
+        "back" => Ok(AttendanceAction::Back),
+        "out" => Ok(AttendanceAction::Out),
         _ => Err(format!("Unknown action: {}. Use: in, break, back, or out", text)),
     }
-}
\ No newline at end of file
+}
+
+/// Builds the follow-up buttons shown after a punch, so the next action is one click away.
+pub fn action_buttons(action: &AttendanceAction) -> Vec<BlockElement> {
+    match action {
+        AttendanceAction::In | AttendanceAction::Back => vec![
+            button("休憩開始", "break", None),
+            button("退勤", "out", Some("danger")),
+        ],
+        AttendanceAction::Break => vec![button("休憩終了", "back", Some("primary"))],
+        AttendanceAction::Out => Vec::new(),
+    }
+}
+
+/// 退勤リマインドの退勤ボタン。value にリマインドした勤務日 (YYYY-MM-DD) を入れる
+pub const REMINDER_OUT_ACTION_ID: &str = "reminder_out";
+
+pub fn reminder_out_button(date: &str) -> BlockElement {
+    BlockElement::Button {
+        text: plain_text("退勤"),
+        action_id: REMINDER_OUT_ACTION_ID.to_string(),
+        value: date.to_string(),
+        style: Some("danger".to_string()),
+    }
+}
+
+pub fn button(label: &str, value: &str, style: Option<&str>) -> BlockElement {
+    BlockElement::Button {
+        text: plain_text(label),
+        action_id: format!("attendance_{}", value),
+        value: value.to_string(),
+        style: style.map(|s| s.to_string()),
+    }
+}
+
+pub fn plain_text(text: &str) -> TextObject {
+    TextObject::PlainText {
+        text: text.to_string(),
+        emoji: true,
+    }
+}
+
+pub fn mrkdwn(text: &str) -> TextObject {
+    TextObject::Mrkdwn {
+        text: text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ボタンの value を、ボタンを押したときと同じように打刻として読む
+    fn punch_of(element: &BlockElement) -> (String, AttendanceAction) {
+        let BlockElement::Button { action_id, value, .. } = element else {
+            panic!("not a button: {:?}", element);
+        };
+        match parse_command(value) {
+            Ok(Command::Punch(punch)) => (action_id.clone(), punch.action),
+            other => panic!("button value {:?} is not a punch: {:?}", value, other),
+        }
+    }
+
+    #[test]
+    fn buttons_after_a_punch_parse_as_the_next_punches() {
+        let after_in: Vec<_> = action_buttons(&AttendanceAction::In).iter().map(punch_of).collect();
+        assert_eq!(
+            after_in,
+            [
+                ("attendance_break".to_string(), AttendanceAction::Break),
+                ("attendance_out".to_string(), AttendanceAction::Out),
+            ]
+        );
+
+        let after_back: Vec<_> = action_buttons(&AttendanceAction::Back).iter().map(punch_of).collect();
+        assert_eq!(after_back, after_in);
+
+        let after_break: Vec<_> = action_buttons(&AttendanceAction::Break).iter().map(punch_of).collect();
+        assert_eq!(after_break, [("attendance_back".to_string(), AttendanceAction::Back)]);
+
+        assert!(action_buttons(&AttendanceAction::Out).is_empty());
+    }
+
+    #[test]
+    fn a_queued_button_click_parses_as_a_punch() {
+        // 受付Lambdaがボタンのクリックをキューに送る形
+        let message: SqsMessage = serde_json::from_value(serde_json::json!({
+            "kind": "block_actions",
+            "command": {
+                "token": "",
+                "team_id": "T0123ABCD",
+                "team_domain": "example",
+                "channel_id": "C0123ABCD",
+                "channel_name": "attendance",
+                "user_id": "U0123ABCD",
+                "user_name": "yamada",
+                "command": "",
+                "text": "break",
+                "response_url": "https://hooks.slack.com/actions/T0123ABCD/1234567890/abcdefABCDEF",
+                "trigger_id": "1234567890.1234567890.abcdef0123456789abcdef0123456789"
+            },
+            "action_id": "attendance_break",
+            "timestamp": "1760598012"
+        }))
+        .unwrap();
+
+        let SqsMessage::BlockActions { command, action_id, .. } = message else {
+            panic!("not a block action: {:?}", message);
+        };
+        assert_eq!(action_id, "attendance_break");
+        assert!(matches!(
+            parse_command(&command.text),
+            Ok(Command::Punch(PunchRequest { action: AttendanceAction::Break, location: None, note: None, work_date: None }))
+        ));
+    }
+
+    #[test]
+    fn reminder_button_carries_the_reminded_date() {
+        let BlockElement::Button { action_id, value, .. } = reminder_out_button("2026-10-16") else {
+            panic!("not a button");
+        };
+        assert_eq!(action_id, REMINDER_OUT_ACTION_ID);
+        // 通常の打刻ボタン（attendance_*）として押した時刻の日付で記録されないこと
+        assert!(!action_id.starts_with("attendance_"));
+        assert_eq!(value, "2026-10-16");
+    }
+
+    #[test]
+    fn rejects_an_unknown_button_value() {
+        assert!(parse_command("lunch").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::report::{format_duration, DaySummary};
+use crate::types::*;
+use std::collections::BTreeMap;
+
+/// プロジェクトの切り替え前の勤務時間の集計先
+pub const UNASSIGNED: &str = "未割当";
+
+/// 勤務区間をプロジェクトの切り替えで区切り、プロジェクトごとの時間（分）を集計する。
+/// 切り替えはその日の中だけ有効で、休憩中の時間は含まない
+pub fn allocate(days: &[DaySummary], switches: &[TaskSwitch]) -> BTreeMap<String, i64> {
+    let mut totals: BTreeMap<String, i64> = BTreeMap::new();
+
+    for day in days {
+        let mut day_switches: Vec<&TaskSwitch> = switches.iter().filter(|s| s.date == day.date).collect();
+        day_switches.sort_by_key(|s| s.timestamp);
+
+        for (start, end) in day.work_sessions() {
+            // 区間の開始時点で有効なプロジェクトと、区間内の切り替えで区切る
+            let mut cursor = start;
+            let mut project = day_switches
+                .iter()
+                .rev()
+                .find(|s| s.timestamp <= start)
+                .map(|s| s.project.as_str())
+                .unwrap_or(UNASSIGNED);
+
+            for switch in day_switches.iter().filter(|s| s.timestamp > start && s.timestamp < end) {
+                *totals.entry(project.to_string()).or_default() += (switch.timestamp - cursor).num_minutes();
+                cursor = switch.timestamp;
+                project = &switch.project;
+            }
+            *totals.entry(project.to_string()).or_default() += (end - cursor).num_minutes();
+        }
+    }
+
+    totals.retain(|_, minutes| *minutes > 0);
+    totals
+}
+
+/// プロジェクト別の時間。切り替えの記録が無い（未割当だけの）場合は None
+pub fn format_allocation(totals: &BTreeMap<String, i64>) -> Option<String> {
+    if totals.keys().all(|project| project == UNASSIGNED) {
+        return None;
+    }
+
+    let mut lines: Vec<String> = totals
+        .iter()
+        .filter(|(project, _)| project.as_str() != UNASSIGNED)
+        .map(|(project, minutes)| format!("・{}  {}", project, format_duration(*minutes)))
+        .collect();
+    if let Some(minutes) = totals.get(UNASSIGNED) {
+        lines.push(format!("・{}  {}", UNASSIGNED, format_duration(*minutes)));
+    }
+
+    Some(format!("プロジェクト別（打刻の勤務区間から計算）\n{}", lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{at, punch, summarize};
+
+    fn day(punches: &[(AttendanceAction, &str)]) -> Vec<DaySummary> {
+        let punches: Vec<PunchRecord> = punches
+            .iter()
+            .map(|(action, time)| punch("2026-10-05", time, action.clone()))
+            .collect();
+        summarize(&punches)
+    }
+
+    fn switch(time: &str, project: &str) -> TaskSwitch {
+        TaskSwitch {
+            user_id: "U1".to_string(),
+            user_name: "user".to_string(),
+            date: "2026-10-05".to_string(),
+            timestamp: at("2026-10-05", time),
+            project: project.to_string(),
+        }
+    }
+
+    #[test]
+    fn splits_sessions_at_switches_and_skips_breaks() {
+        let days = day(&[
+            (AttendanceAction::In, "09:00"),
+            (AttendanceAction::Break, "12:00"),
+            (AttendanceAction::Back, "13:00"),
+            (AttendanceAction::Out, "18:00"),
+        ]);
+        let switches = [switch("10:00", "PROJ-1"), switch("12:30", "PROJ-2"), switch("16:00", "PROJ-1")];
+
+        let totals = allocate(&days, &switches);
+        // 09:00〜10:00 未割当、10:00〜12:00 と 16:00〜18:00 が PROJ-1、13:00〜16:00 が PROJ-2
+        assert_eq!(totals.get(UNASSIGNED), Some(&60));
+        assert_eq!(totals.get("PROJ-1"), Some(&240));
+        assert_eq!(totals.get("PROJ-2"), Some(&180));
+    }
+
+    #[test]
+    fn switches_on_other_days_do_not_apply() {
+        let days = day(&[(AttendanceAction::In, "09:00"), (AttendanceAction::Out, "10:00")]);
+        let mut other_day = switch("08:00", "PROJ-1");
+        other_day.date = "2026-10-04".to_string();
+
+        let totals = allocate(&days, &[other_day]);
+        assert_eq!(totals.get(UNASSIGNED), Some(&60));
+        assert_eq!(format_allocation(&totals), None);
+    }
+}
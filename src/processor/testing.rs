@@ -0,0 +1,33 @@
+//! Fixtures shared by the unit tests.
+
+use crate::jst;
+use crate::policy::WorkPolicy;
+use crate::report::{summarize_days, DaySummary};
+use crate::types::{AttendanceAction, PunchRecord};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+
+/// `YYYY-MM-DD` と `HH:MM` の JST 日時
+pub fn at(date: &str, time: &str) -> DateTime<FixedOffset> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+    let time = NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+    jst::datetime(date, time).unwrap()
+}
+
+/// ユーザー U1 の打刻
+pub fn punch(date: &str, time: &str, action: AttendanceAction) -> PunchRecord {
+    PunchRecord {
+        page_id: String::new(),
+        user_id: "U1".to_string(),
+        user_name: "user".to_string(),
+        date: date.to_string(),
+        action,
+        timestamp: at(date, time),
+        location: None,
+        note: None,
+    }
+}
+
+/// 既定の勤務ルールでの日ごとの集計
+pub fn summarize(punches: &[PunchRecord]) -> Vec<DaySummary> {
+    summarize_days(punches, &WorkPolicy::default(), None)
+}
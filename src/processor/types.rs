@@ -16,13 +16,215 @@ pub struct SlackCommand {
     pub trigger_id: String,
 }
 
+/// Message body queued by the receiver. `kind` selects the handler.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SqsMessage {
+    SlashCommand {
+        command: SlackCommand,
+        timestamp: String,
+    },
+    BlockActions {
+        command: SlackCommand,
+        action_id: String,
+        timestamp: String,
+    },
+    ViewSubmission {
+        user: InteractionUser,
+        team: InteractionTeam,
+        view: View,
+        timestamp: String,
+    },
+    Shortcut {
+        user: InteractionUser,
+        team: InteractionTeam,
+        callback_id: String,
+        trigger_id: String,
+        timestamp: String,
+    },
+    /// `/attendance fix`: the receiver has already opened a loading modal with this view id.
+    FixModal {
+        command: SlackCommand,
+        view_id: String,
+        timestamp: String,
+    },
+    /// Events API callback (messages and reactions)
+    Event {
+        team_id: String,
+        event: SlackEvent,
+        timestamp: String,
+    },
+}
+
+impl SqsMessage {
+    /// Slack team the message came from, used to pick that workspace's credentials.
+    pub fn team_id(&self) -> &str {
+        match self {
+            SqsMessage::SlashCommand { command, .. }
+            | SqsMessage::BlockActions { command, .. }
+            | SqsMessage::FixModal { command, .. } => &command.team_id,
+            SqsMessage::ViewSubmission { team, .. } | SqsMessage::Shortcut { team, .. } => &team.id,
+            SqsMessage::Event { team_id, .. } => team_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SlackEvent {
+    Message {
+        channel: String,
+        #[serde(default)]
+        user: Option<String>,
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        subtype: Option<String>,
+        #[serde(default)]
+        bot_id: Option<String>,
+    },
+    ReactionAdded {
+        user: String,
+        reaction: String,
+        item: ReactionItem,
+    },
+    AppHomeOpened {
+        user: String,
+        #[serde(default)]
+        tab: String,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ReactionItem {
+    #[serde(default)]
+    pub channel: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InteractionUser {
+    pub id: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+impl InteractionUser {
+    pub fn display_name(&self) -> &str {
+        if self.username.is_empty() {
+            &self.name
+        } else {
+            &self.username
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InteractionTeam {
+    pub id: String,
+    #[serde(default)]
+    pub domain: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct View {
+    pub id: String,
+    #[serde(default)]
+    pub callback_id: String,
+    #[serde(default)]
+    pub private_metadata: String,
+    #[serde(default)]
+    pub state: serde_json::Value,
+}
+
 #[derive(Debug, Serialize)]
 pub struct SlackResponse {
     pub response_type: String,
     pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<Vec<Block>>,
 }
 
 #[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Block {
+    Header { text: TextObject },
+    Section { text: TextObject },
+    Actions { elements: Vec<BlockElement> },
+    Context { elements: Vec<TextObject> },
+    Divider,
+    Input {
+        block_id: String,
+        label: TextObject,
+        element: BlockElement,
+        optional: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TextObject {
+    PlainText { text: String, emoji: bool },
+    Mrkdwn { text: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockElement {
+    Button {
+        text: TextObject,
+        action_id: String,
+        value: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        style: Option<String>,
+    },
+    Timepicker {
+        action_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        initial_time: Option<String>,
+    },
+    PlainTextInput {
+        action_id: String,
+        multiline: bool,
+    },
+}
+
+/// Views opened with views.open / views.update, or published to the App Home tab.
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SlackView {
+    Home {
+        blocks: Vec<Block>,
+    },
+    Modal {
+        callback_id: String,
+        title: TextObject,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        submit: Option<TextObject>,
+        close: TextObject,
+        private_metadata: String,
+        blocks: Vec<Block>,
+    },
+}
+
+/// State carried through the correction modal's `private_metadata`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FixModalMetadata {
+    pub date: String,
+    pub punches: Vec<FixModalPunch>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FixModalPunch {
+    pub page_id: String,
+    pub action: AttendanceAction,
+    pub time: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum AttendanceAction {
     #[serde(rename = "in")]
     In,
@@ -34,6 +236,269 @@ pub enum AttendanceAction {
     Out,
 }
 
+impl AttendanceAction {
+    /// Notionの「アクション」セレクトに保存する名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            AttendanceAction::In => "出勤",
+            AttendanceAction::Break => "休憩入り",
+            AttendanceAction::Back => "休憩戻り",
+            AttendanceAction::Out => "退勤",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "出勤" => Some(AttendanceAction::In),
+            "休憩入り" => Some(AttendanceAction::Break),
+            "休憩戻り" => Some(AttendanceAction::Back),
+            "退勤" => Some(AttendanceAction::Out),
+            _ => None,
+        }
+    }
+}
+
+/// 勤務場所（通勤手当の計算用に出勤時に記録する）
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WorkLocation {
+    Remote,
+    Office,
+    /// 客先（`client:ACME` の ACME）
+    Client(String),
+}
+
+impl WorkLocation {
+    /// `in remote` / `in office` / `in client:ACME` の指定
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_lowercase().as_str() {
+            "remote" | "リモート" => Some(WorkLocation::Remote),
+            "office" | "オフィス" => Some(WorkLocation::Office),
+            _ => text
+                .split_once(':')
+                .filter(|(kind, name)| kind.eq_ignore_ascii_case("client") && !name.is_empty())
+                .map(|(_, name)| WorkLocation::Client(name.to_string())),
+        }
+    }
+
+    /// Notionの「勤務場所」セレクトに保存する名前
+    pub fn label(&self) -> String {
+        match self {
+            WorkLocation::Remote => "リモート".to_string(),
+            WorkLocation::Office => "オフィス".to_string(),
+            WorkLocation::Client(name) => format!("客先:{}", name),
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "リモート" => Some(WorkLocation::Remote),
+            "オフィス" => Some(WorkLocation::Office),
+            _ => label.strip_prefix("客先:").map(|name| WorkLocation::Client(name.to_string())),
+        }
+    }
+}
+
+/// 休暇・欠勤の種類。打刻と同じデータベースに「アクション」として保存する
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LeaveType {
+    Paid,
+    HalfAm,
+    HalfPm,
+    Sick,
+    Special,
+    Absence,
+}
+
+impl LeaveType {
+    /// `/attendance leave` で指定する名前
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_lowercase().as_str() {
+            "paid" | "有給" => Some(LeaveType::Paid),
+            "half-am" | "am" | "午前半休" => Some(LeaveType::HalfAm),
+            "half-pm" | "pm" | "午後半休" => Some(LeaveType::HalfPm),
+            "sick" | "病欠" => Some(LeaveType::Sick),
+            "special" | "特別休暇" => Some(LeaveType::Special),
+            "absence" | "欠勤" => Some(LeaveType::Absence),
+            _ => None,
+        }
+    }
+
+    /// Notionの「アクション」セレクトに保存する名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            LeaveType::Paid => "有給休暇",
+            LeaveType::HalfAm => "午前半休",
+            LeaveType::HalfPm => "午後半休",
+            LeaveType::Sick => "病気休暇",
+            LeaveType::Special => "特別休暇",
+            LeaveType::Absence => "欠勤",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::all().into_iter().find(|t| t.label() == label)
+    }
+
+    pub fn all() -> [LeaveType; 6] {
+        [
+            LeaveType::Paid,
+            LeaveType::HalfAm,
+            LeaveType::HalfPm,
+            LeaveType::Sick,
+            LeaveType::Special,
+            LeaveType::Absence,
+        ]
+    }
+
+    /// 休暇の日数（半休は0.5日）
+    pub fn days(&self) -> f64 {
+        match self {
+            LeaveType::HalfAm | LeaveType::HalfPm => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// 年次有給休暇の残日数から差し引く休暇か
+    pub fn is_paid(&self) -> bool {
+        matches!(self, LeaveType::Paid | LeaveType::HalfAm | LeaveType::HalfPm)
+    }
+
+    /// 同じ日に両方は登録できない組み合わせか。午前半休と午後半休だけは同じ日に登録できる
+    pub fn conflicts_with(&self, other: &LeaveType) -> bool {
+        !matches!(
+            (self, other),
+            (LeaveType::HalfAm, LeaveType::HalfPm) | (LeaveType::HalfPm, LeaveType::HalfAm)
+        )
+    }
+
+    /// 1日を通して勤務しない休暇か（欠勤は休暇として扱わない）
+    pub fn is_full_day_leave(&self) -> bool {
+        matches!(self, LeaveType::Paid | LeaveType::Sick | LeaveType::Special)
+    }
+}
+
+/// Notionに保存された休暇・欠勤
+#[derive(Debug, Clone)]
+pub struct LeaveRecord {
+    pub page_id: String,
+    pub user_id: String,
+    pub user_name: String,
+    /// 「日付」プロパティ (YYYY-MM-DD)
+    pub date: String,
+    pub leave_type: LeaveType,
+}
+
+/// 有給休暇の付与を保存する「アクション」の名前
+pub const LEAVE_GRANT_LABEL: &str = "有給付与";
+
+/// 年次有給休暇の付与。付与日から2年で時効により消滅する
+#[derive(Debug, Clone)]
+pub struct LeaveGrant {
+    pub user_id: String,
+    pub user_name: String,
+    /// 付与日 (YYYY-MM-DD)
+    pub date: String,
+    pub days: f64,
+}
+
+/// プロジェクトの切り替えを保存する「アクション」の名前
+pub const TASK_SWITCH_LABEL: &str = "タスク切替";
+
+/// `/attendance task` によるプロジェクトの切り替え。次の切り替えまでの勤務時間をそのプロジェクトに割り当てる
+#[derive(Debug, Clone)]
+pub struct TaskSwitch {
+    pub user_id: String,
+    pub user_name: String,
+    /// 「日付」プロパティ (YYYY-MM-DD)
+    pub date: String,
+    pub timestamp: DateTime<FixedOffset>,
+    pub project: String,
+}
+
+/// 打刻修正の申請を保存する「アクション」の名前
+pub const CORRECTION_REQUEST_LABEL: &str = "打刻修正申請";
+
+/// 打刻修正の申請の「承認状態」
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalStatus {
+    Pending,
+    /// 承認者が承認・却下の処理を始めた申請。処理中は他の承認者は操作できない
+    Processing,
+    Approved,
+    Rejected,
+}
+
+impl ApprovalStatus {
+    /// Notionの「承認状態」セレクトに保存する名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "承認待ち",
+            ApprovalStatus::Processing => "処理中",
+            ApprovalStatus::Approved => "承認済み",
+            ApprovalStatus::Rejected => "却下",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "承認待ち" => Some(ApprovalStatus::Pending),
+            "処理中" => Some(ApprovalStatus::Processing),
+            "承認済み" => Some(ApprovalStatus::Approved),
+            "却下" => Some(ApprovalStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// 申請された修正の内容。承認されるまで打刻には反映せず、「修正内容」に JSON で保存する
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CorrectionDetail {
+    /// 既存の打刻の時刻変更
+    pub changes: Vec<CorrectionChange>,
+    /// 追加する打刻
+    pub additions: Vec<CorrectionAddition>,
+}
+
+impl CorrectionDetail {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.additions.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CorrectionChange {
+    pub page_id: String,
+    pub action: AttendanceAction,
+    pub original: DateTime<FixedOffset>,
+    pub corrected: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CorrectionAddition {
+    pub action: AttendanceAction,
+    pub timestamp: DateTime<FixedOffset>,
+}
+
+/// `/attendance fix` のモーダルから出された打刻修正の申請
+#[derive(Debug, Clone)]
+pub struct CorrectionRequest {
+    /// 申請のページID（作成前は空）
+    pub page_id: String,
+    pub user_id: String,
+    pub user_name: String,
+    /// 修正する日 (YYYY-MM-DD)
+    pub date: String,
+    pub reason: String,
+    pub requested_at: DateTime<FixedOffset>,
+    pub status: ApprovalStatus,
+    pub detail: CorrectionDetail,
+    /// 承認・却下したユーザーのID（処理中は処理を始めたユーザーのID）
+    pub approver_id: Option<String>,
+    /// 承認の反映で追加した打刻のページID。`detail.additions` と同じ順に並ぶ
+    pub applied_page_ids: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AttendanceRecord {
     pub user_id: String,
@@ -41,12 +506,35 @@ pub struct AttendanceRecord {
     pub action: AttendanceAction,
     pub timestamp: DateTime<FixedOffset>,
     pub date: String,
+    /// 修正モーダルから追加された打刻の場合の修正理由
+    pub correction_reason: Option<String>,
+    /// 出勤時に指定された勤務場所
+    pub location: Option<WorkLocation>,
+    /// 打刻に添えたメモ（早退の理由など）
+    pub note: Option<String>,
+    /// 管理者が代理で登録した場合の管理者のユーザーID
+    pub recorded_by: Option<String>,
+}
+
+/// A punch already stored in Notion, with the page id needed to update it.
+#[derive(Debug, Clone)]
+pub struct PunchRecord {
+    pub page_id: String,
+    pub user_id: String,
+    pub user_name: String,
+    /// 「日付」プロパティ (YYYY-MM-DD)
+    pub date: String,
+    pub action: AttendanceAction,
+    pub timestamp: DateTime<FixedOffset>,
+    pub location: Option<WorkLocation>,
+    /// 「メモ」プロパティ
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
-pub struct NotionPageRequest {
+pub struct NotionPageRequest<P = NotionProperties> {
     pub parent: NotionParent,
-    pub properties: NotionProperties,
+    pub properties: P,
 }
 
 #[derive(Debug, Serialize)]
@@ -66,6 +554,93 @@ pub struct NotionProperties {
     pub timestamp: NotionDate,
     #[serde(rename = "日付")]
     pub date: NotionRichText,
+    #[serde(rename = "修正理由", skip_serializing_if = "Option::is_none")]
+    pub correction_reason: Option<NotionRichText>,
+    #[serde(rename = "付与日数", skip_serializing_if = "Option::is_none")]
+    pub granted_days: Option<NotionNumber>,
+    #[serde(rename = "勤務場所", skip_serializing_if = "Option::is_none")]
+    pub location: Option<NotionSelect>,
+    #[serde(rename = "プロジェクト", skip_serializing_if = "Option::is_none")]
+    pub project: Option<NotionRichText>,
+    #[serde(rename = "メモ", skip_serializing_if = "Option::is_none")]
+    pub note: Option<NotionRichText>,
+    #[serde(rename = "登録者", skip_serializing_if = "Option::is_none")]
+    pub recorded_by: Option<NotionRichText>,
+    #[serde(rename = "承認状態", skip_serializing_if = "Option::is_none")]
+    pub approval_status: Option<NotionSelect>,
+    #[serde(rename = "修正内容", skip_serializing_if = "Option::is_none")]
+    pub correction_detail: Option<NotionRichText>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotionPageUpdateRequest<P> {
+    pub properties: P,
+}
+
+/// Properties written when a punch is corrected. The previous time and correction
+/// timestamp are kept on the page as an audit trail.
+#[derive(Debug, Serialize)]
+pub struct NotionCorrectionProperties {
+    #[serde(rename = "タイムスタンプ")]
+    pub timestamp: NotionDate,
+    #[serde(rename = "修正理由")]
+    pub correction_reason: NotionRichText,
+    #[serde(rename = "修正前時刻")]
+    pub original_timestamp: NotionRichText,
+    #[serde(rename = "修正日時")]
+    pub corrected_at: NotionDate,
+}
+
+/// A page in the audit log database (`NOTION_AUDIT_DATABASE_ID`).
+#[derive(Debug, Serialize)]
+pub struct NotionAuditProperties {
+    #[serde(rename = "ユーザーID")]
+    pub user_id: NotionTitle,
+    #[serde(rename = "ユーザー名")]
+    pub user_name: NotionRichText,
+    #[serde(rename = "種類")]
+    pub kind: NotionSelect,
+    #[serde(rename = "入力")]
+    pub input: NotionRichText,
+    #[serde(rename = "コマンド")]
+    pub command: NotionRichText,
+    #[serde(rename = "結果")]
+    pub result: NotionRichText,
+    #[serde(rename = "ページID")]
+    pub page_ids: NotionRichText,
+    #[serde(rename = "エラー")]
+    pub error: NotionRichText,
+    #[serde(rename = "日時")]
+    pub timestamp: NotionDate,
+    #[serde(rename = "日付")]
+    pub date: NotionRichText,
+}
+
+/// Properties written when an approver starts deciding a correction request.
+#[derive(Debug, Serialize)]
+pub struct NotionClaimProperties {
+    #[serde(rename = "承認状態")]
+    pub status: NotionSelect,
+    #[serde(rename = "承認者")]
+    pub approver: NotionRichText,
+}
+
+/// Pages already created while applying an approved correction request.
+#[derive(Debug, Serialize)]
+pub struct NotionAppliedPagesProperties {
+    #[serde(rename = "反映済みページ")]
+    pub applied_page_ids: NotionRichText,
+}
+
+/// Properties written when a correction request is approved or rejected.
+#[derive(Debug, Serialize)]
+pub struct NotionApprovalProperties {
+    #[serde(rename = "承認状態")]
+    pub status: NotionSelect,
+    #[serde(rename = "承認者")]
+    pub approver: NotionRichText,
+    #[serde(rename = "承認日時")]
+    pub decided_at: NotionDate,
 }
 
 #[derive(Debug, Serialize)]
@@ -88,6 +663,11 @@ pub struct NotionText {
     pub content: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct NotionNumber {
+    pub number: f64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct NotionSelect {
     pub select: NotionOption,
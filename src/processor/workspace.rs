@@ -0,0 +1,304 @@
+use crate::installation::InstallationStore;
+use crate::notion::NotionClient;
+use crate::slack::SlackClient;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Credentials for one Slack workspace: where its records live and how to call Slack.
+#[derive(Clone, Default)]
+pub struct Workspace {
+    /// Slack team ID. Empty for the single-workspace settings read from the plain environment variables
+    pub team_id: String,
+    pub notion_api_key: String,
+    pub notion_database_id: String,
+    pub notion_audit_database_id: Option<String>,
+    pub slack_bot_token: String,
+}
+
+impl Workspace {
+    /// `NOTION_API_KEY` / `NOTION_DATABASE_ID` / `NOTION_AUDIT_DATABASE_ID` / `SLACK_BOT_TOKEN`
+    pub fn from_env() -> Self {
+        Self {
+            team_id: String::new(),
+            notion_api_key: std::env::var("NOTION_API_KEY").unwrap_or_default(),
+            notion_database_id: std::env::var("NOTION_DATABASE_ID").unwrap_or_default(),
+            notion_audit_database_id: std::env::var("NOTION_AUDIT_DATABASE_ID")
+                .ok()
+                .filter(|id| !id.is_empty()),
+            slack_bot_token: std::env::var("SLACK_BOT_TOKEN").unwrap_or_default(),
+        }
+    }
+
+    pub fn notion_client(&self) -> Result<NotionClient, Box<dyn std::error::Error + Send + Sync>> {
+        if self.notion_api_key.is_empty() || self.notion_database_id.is_empty() {
+            return Err(format!("Notion is not configured for workspace `{}`", self.team_id).into());
+        }
+
+        Ok(NotionClient::new(
+            self.notion_api_key.clone(),
+            self.notion_database_id.clone(),
+        ))
+    }
+
+    /// Client for the audit log database, if this workspace has one.
+    pub fn audit_client(&self) -> Option<NotionClient> {
+        let database_id = self.notion_audit_database_id.clone()?;
+        (!self.notion_api_key.is_empty()).then(|| NotionClient::new(self.notion_api_key.clone(), database_id))
+    }
+
+    pub fn slack_client(&self) -> Result<SlackClient, Box<dyn std::error::Error + Send + Sync>> {
+        if self.slack_bot_token.is_empty() {
+            return Err(format!("Slack bot token is not configured for workspace `{}`", self.team_id).into());
+        }
+
+        Ok(SlackClient::new(self.slack_bot_token.clone()))
+    }
+
+    /// Team-specific values override the defaults; anything left out falls back to them.
+    fn with_overrides(&self, team_id: &str, team: &TeamConfig) -> Self {
+        Self {
+            team_id: team_id.to_string(),
+            notion_api_key: team.notion_api_key.clone().unwrap_or_else(|| self.notion_api_key.clone()),
+            notion_database_id: team
+                .notion_database_id
+                .clone()
+                .unwrap_or_else(|| self.notion_database_id.clone()),
+            notion_audit_database_id: team
+                .notion_audit_database_id
+                .clone()
+                .or_else(|| self.notion_audit_database_id.clone()),
+            slack_bot_token: team.slack_bot_token.clone().unwrap_or_else(|| self.slack_bot_token.clone()),
+        }
+    }
+}
+
+// Secrets must never end up in logs, so only the team id is printed.
+impl std::fmt::Debug for Workspace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Workspace").field("team_id", &self.team_id).finish_non_exhaustive()
+    }
+}
+
+/// One entry of `TEAM_CONFIGS` (or an installed team). The receiver reads `signing_secret` from the same JSON.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TeamConfig {
+    #[serde(default)]
+    notion_api_key: Option<String>,
+    #[serde(default)]
+    notion_database_id: Option<String>,
+    #[serde(default)]
+    notion_audit_database_id: Option<String>,
+    #[serde(default)]
+    slack_bot_token: Option<String>,
+}
+
+/// Per-team credentials keyed by Slack team ID (`TEAM_CONFIGS`), on top of the
+/// single-workspace environment variables. The latter serve only `DEFAULT_TEAM_ID`, or
+/// every team while `TEAM_CONFIGS` is empty and no `DEFAULT_TEAM_ID` is set.
+#[derive(Debug, Clone, Default)]
+pub struct Workspaces {
+    default: Workspace,
+    /// Team served by the single-workspace settings (`DEFAULT_TEAM_ID`)
+    default_team_id: Option<String>,
+    teams: BTreeMap<String, TeamConfig>,
+    /// Where bot tokens of teams that installed the app through the OAuth flow are stored
+    installations: Option<InstallationStore>,
+    /// Bot tokens loaded from `installations`, keyed by team ID
+    installed: BTreeMap<String, String>,
+}
+
+impl Workspaces {
+    /// `TEAM_CONFIGS` 例: `{"T0123":{"notion_api_key":"secret_...","notion_database_id":"...","slack_bot_token":"xoxb-..."}}`
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let teams = match std::env::var("TEAM_CONFIGS") {
+            Ok(json) if !json.trim().is_empty() => {
+                serde_json::from_str(&json).map_err(|e| format!("TEAM_CONFIGS: {}", e))?
+            }
+            _ => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            default: Workspace::from_env(),
+            default_team_id: std::env::var("DEFAULT_TEAM_ID").ok().filter(|id| !id.is_empty()),
+            teams,
+            installations: InstallationStore::from_env(),
+            installed: BTreeMap::new(),
+        })
+    }
+
+    /// Picks up the bot token the team stored when installing the app, unless
+    /// `TEAM_CONFIGS` already gives it one. Call before `for_team`.
+    pub async fn load_installation(&mut self, team_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(store) = &self.installations else {
+            return Ok(());
+        };
+        if team_id.is_empty() || self.teams.get(team_id).is_some_and(|team| team.slack_bot_token.is_some()) {
+            return Ok(());
+        }
+
+        if let Some(token) = store.bot_token(team_id).await? {
+            self.installed.insert(team_id.to_string(), token);
+        }
+        Ok(())
+    }
+
+    /// Picks up the bot tokens of every installed team, for `all`.
+    pub async fn load_installations(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(store) = &self.installations else {
+            return Ok(());
+        };
+
+        self.installed = store.bot_tokens().await?;
+        Ok(())
+    }
+
+    /// Credentials for the team a message came from. Installing the app does not give a team
+    /// anywhere to keep its records, so a team that is neither in `TEAM_CONFIGS` nor served
+    /// by the single-workspace settings is an error.
+    pub fn for_team(&self, team_id: &str) -> Result<Workspace, Box<dyn std::error::Error + Send + Sync>> {
+        let team = self.teams.get(team_id);
+        let mut workspace = match team {
+            Some(team) => self.default.with_overrides(team_id, team),
+            None if self.is_default_team(team_id) => Workspace {
+                team_id: team_id.to_string(),
+                ..self.default.clone()
+            },
+            None => {
+                return Err(format!(
+                    "workspace not configured: team `{}` has no entry in TEAM_CONFIGS and is not DEFAULT_TEAM_ID",
+                    team_id
+                )
+                .into())
+            }
+        };
+
+        // TEAM_CONFIGS のトークン、インストール時のトークン、SLACK_BOT_TOKEN の順
+        if team.is_none_or(|team| team.slack_bot_token.is_none()) {
+            if let Some(token) = self.installed.get(team_id) {
+                workspace.slack_bot_token = token.clone();
+            }
+        }
+        Ok(workspace)
+    }
+
+    /// Whether the single-workspace settings serve the team. Without `DEFAULT_TEAM_ID` they
+    /// serve every team until `TEAM_CONFIGS` is used, except teams that installed the app.
+    fn is_default_team(&self, team_id: &str) -> bool {
+        match &self.default_team_id {
+            Some(default_team_id) => default_team_id == team_id,
+            None => self.teams.is_empty() && !self.installed.contains_key(team_id),
+        }
+    }
+
+    /// The single-workspace settings, when they serve some team.
+    fn default_workspace(&self) -> Option<Workspace> {
+        if self.default.notion_database_id.is_empty() {
+            return None;
+        }
+        match &self.default_team_id {
+            Some(team_id) => self.for_team(team_id).ok(),
+            None => self.teams.is_empty().then(|| self.default.clone()),
+        }
+    }
+
+    /// Every configured workspace, for scheduled jobs that are not triggered by a team.
+    /// Workspaces with the same database and bot token count once. Teams that only
+    /// installed the app are not configured, so they are left out.
+    pub fn all(&self) -> Vec<Workspace> {
+        let teams = self.teams.keys().filter_map(|team_id| self.for_team(team_id).ok());
+
+        let mut workspaces: Vec<Workspace> = Vec::new();
+        for workspace in teams.chain(self.default_workspace()) {
+            let duplicate = workspaces.iter().any(|w| {
+                w.notion_database_id == workspace.notion_database_id && w.slack_bot_token == workspace.slack_bot_token
+            });
+            if !duplicate {
+                workspaces.push(workspace);
+            }
+        }
+
+        workspaces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspaces(default_team_id: Option<&str>, teams: &[(&str, Option<&str>, Option<&str>)]) -> Workspaces {
+        Workspaces {
+            default: Workspace {
+                notion_api_key: "secret_default".to_string(),
+                notion_database_id: "db-default".to_string(),
+                slack_bot_token: "xoxb-default".to_string(),
+                ..Workspace::default()
+            },
+            default_team_id: default_team_id.map(str::to_string),
+            teams: teams
+                .iter()
+                .map(|(team_id, database_id, token)| {
+                    let team = TeamConfig {
+                        notion_database_id: database_id.map(str::to_string),
+                        slack_bot_token: token.map(str::to_string),
+                        ..TeamConfig::default()
+                    };
+                    (team_id.to_string(), team)
+                })
+                .collect(),
+            installations: None,
+            installed: BTreeMap::new(),
+        }
+    }
+
+    fn team_ids(workspaces: &Workspaces) -> Vec<String> {
+        workspaces.all().into_iter().map(|w| w.team_id).collect()
+    }
+
+    #[test]
+    fn keeps_the_default_when_a_team_shares_its_database_with_another_token() {
+        let workspaces = workspaces(Some("T0"), &[("T1", None, Some("xoxb-t1"))]);
+        assert_eq!(team_ids(&workspaces), ["T1", "T0"]);
+    }
+
+    #[test]
+    fn counts_the_same_database_and_token_once() {
+        let workspaces = workspaces(Some("T0"), &[("T1", None, None), ("T2", Some("db-2"), Some("xoxb-t2"))]);
+        assert_eq!(team_ids(&workspaces), ["T1", "T2"]);
+    }
+
+    #[test]
+    fn installed_teams_without_team_configs_are_not_configured() {
+        let mut workspaces = workspaces(Some("T0"), &[]);
+        workspaces.installed.insert("T9".to_string(), "xoxb-t9".to_string());
+
+        let error = workspaces.for_team("T9").unwrap_err();
+        assert!(error.to_string().starts_with("workspace not configured"));
+        assert_eq!(team_ids(&workspaces), ["T0"]);
+    }
+
+    #[test]
+    fn the_default_team_uses_its_installed_token() {
+        let mut workspaces = workspaces(Some("T0"), &[]);
+        workspaces.installed.insert("T0".to_string(), "xoxb-t0".to_string());
+
+        let workspace = workspaces.for_team("T0").unwrap();
+        assert_eq!(workspace.notion_database_id, "db-default");
+        assert_eq!(workspace.slack_bot_token, "xoxb-t0");
+    }
+
+    #[test]
+    fn without_team_configs_the_defaults_serve_any_team_that_did_not_install() {
+        let mut workspaces = workspaces(None, &[]);
+        workspaces.installed.insert("T9".to_string(), "xoxb-t9".to_string());
+
+        assert_eq!(workspaces.for_team("T1").unwrap().notion_database_id, "db-default");
+        assert!(workspaces.for_team("T9").is_err());
+    }
+
+    #[test]
+    fn with_team_configs_unlisted_teams_are_not_configured() {
+        let workspaces = workspaces(None, &[("T1", Some("db-1"), Some("xoxb-t1"))]);
+        assert!(workspaces.for_team("T2").is_err());
+        assert_eq!(team_ids(&workspaces), ["T1"]);
+    }
+}
@@ -0,0 +1,52 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+
+/// Slack retries an event three times within a few minutes, so a claim can expire after an hour.
+const CLAIM_TTL_SECS: i64 = 60 * 60;
+
+/// DynamoDB table with one item per Events API delivery that was queued (`SLACK_EVENTS_TABLE`).
+fn events_table() -> Option<String> {
+    std::env::var("SLACK_EVENTS_TABLE").ok().filter(|t| !t.is_empty())
+}
+
+/// Claims the event before it is queued. Returns false when an earlier delivery of the same
+/// `event_id` already claimed it. Without `SLACK_EVENTS_TABLE` every delivery is queued.
+pub async fn claim(event_id: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(table) = events_table() else {
+        return Ok(true);
+    };
+    let config = aws_config::load_from_env().await;
+    let client = aws_sdk_dynamodb::Client::new(&config);
+
+    let result = client
+        .put_item()
+        .table_name(table)
+        .item("event_id", AttributeValue::S(event_id.to_string()))
+        .item("expires_at", AttributeValue::N((chrono::Utc::now().timestamp() + CLAIM_TTL_SECS).to_string()))
+        .condition_expression("attribute_not_exists(event_id)")
+        .send()
+        .await;
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_conditional_check_failed_exception()) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Drops the claim of an event that could not be queued, so Slack's retry is queued instead.
+pub async fn release(event_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(table) = events_table() else {
+        return Ok(());
+    };
+    let config = aws_config::load_from_env().await;
+    let client = aws_sdk_dynamodb::Client::new(&config);
+
+    client
+        .delete_item()
+        .table_name(table)
+        .key("event_id", AttributeValue::S(event_id.to_string()))
+        .send()
+        .await?;
+
+    Ok(())
+}
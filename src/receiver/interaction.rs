@@ -0,0 +1,134 @@
+use crate::types::*;
+
+/// Routes an interaction payload to the queue message kind the processor expects.
+/// Returns `None` for interactions this app does not handle.
+pub fn to_sqs_message(payload: InteractionPayload, timestamp: &str) -> Option<SqsMessage> {
+    match payload {
+        InteractionPayload::BlockActions(block_actions) => {
+            let action = block_actions.actions.first()?;
+            Some(SqsMessage::BlockActions {
+                command: command_from_block_actions(&block_actions, &action.value),
+                action_id: action.action_id.clone(),
+                timestamp: timestamp.to_string(),
+            })
+        }
+        InteractionPayload::ViewSubmission(submission) => Some(SqsMessage::ViewSubmission {
+            user: submission.user,
+            team: submission.team,
+            view: submission.view,
+            timestamp: timestamp.to_string(),
+        }),
+        InteractionPayload::Shortcut(shortcut) => Some(SqsMessage::Shortcut {
+            user: shortcut.user,
+            team: shortcut.team,
+            callback_id: shortcut.callback_id,
+            trigger_id: shortcut.trigger_id,
+            timestamp: timestamp.to_string(),
+        }),
+        InteractionPayload::Unsupported => None,
+    }
+}
+
+/// Builds the same command the slash command would have produced, so a button click
+/// can reuse the processor's command handling.
+fn command_from_block_actions(payload: &BlockActionsPayload, text: &str) -> SlackCommand {
+    let user_name = if payload.user.username.is_empty() {
+        payload.user.name.clone()
+    } else {
+        payload.user.username.clone()
+    };
+
+    SlackCommand {
+        token: String::new(),
+        team_id: payload.team.id.clone(),
+        team_domain: payload.team.domain.clone(),
+        channel_id: payload.channel.as_ref().map(|c| c.id.clone()).unwrap_or_default(),
+        channel_name: payload.channel.as_ref().map(|c| c.name.clone()).unwrap_or_default(),
+        user_id: payload.user.id.clone(),
+        user_name,
+        command: String::new(),
+        text: text.to_string(),
+        response_url: payload.response_url.clone().unwrap_or_default(),
+        trigger_id: payload.trigger_id.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMESTAMP: &str = "1760598012";
+
+    fn route(fixture: &str) -> Option<serde_json::Value> {
+        let payload: InteractionPayload = serde_json::from_str(fixture).unwrap();
+        to_sqs_message(payload, TIMESTAMP).map(|message| serde_json::to_value(message).unwrap())
+    }
+
+    #[test]
+    fn routes_a_button_click_as_the_command_it_stands_for() {
+        let message = route(include_str!("testdata/block_actions.json")).unwrap();
+
+        assert_eq!(message["kind"], "block_actions");
+        assert_eq!(message["action_id"], "attendance_break");
+        assert_eq!(message["timestamp"], TIMESTAMP);
+        let command = &message["command"];
+        assert_eq!(command["text"], "break");
+        assert_eq!(command["team_id"], "T0123ABCD");
+        assert_eq!(command["team_domain"], "example");
+        assert_eq!(command["channel_id"], "C0123ABCD");
+        assert_eq!(command["channel_name"], "attendance");
+        assert_eq!(command["user_id"], "U0123ABCD");
+        assert_eq!(command["user_name"], "yamada");
+        assert_eq!(command["response_url"], "https://hooks.slack.com/actions/T0123ABCD/1234567890/abcdefABCDEF");
+        assert_eq!(command["trigger_id"], "1234567890.1234567890.abcdef0123456789abcdef0123456789");
+    }
+
+    #[test]
+    fn routes_a_modal_submission_with_its_view() {
+        let message = route(include_str!("testdata/view_submission.json")).unwrap();
+
+        assert_eq!(message["kind"], "view_submission");
+        assert_eq!(message["user"]["id"], "U0123ABCD");
+        assert_eq!(message["team"]["id"], "T0123ABCD");
+        assert_eq!(message["view"]["id"], "V0123ABCD");
+        assert_eq!(message["view"]["callback_id"], "attendance_fix");
+        assert_eq!(message["view"]["private_metadata"], "2026-10-16");
+        assert_eq!(
+            message["view"]["state"]["values"]["reason"]["reason_input"]["value"],
+            "退勤の打刻忘れ"
+        );
+    }
+
+    #[test]
+    fn routes_a_global_shortcut() {
+        let message = route(include_str!("testdata/shortcut.json")).unwrap();
+
+        assert_eq!(message["kind"], "shortcut");
+        assert_eq!(message["callback_id"], "attendance_in");
+        assert_eq!(message["user"]["id"], "U0123ABCD");
+        assert_eq!(message["user"]["username"], "yamada");
+        assert_eq!(message["team"]["id"], "T0123ABCD");
+        assert_eq!(message["trigger_id"], "1234567890.1234567890.abcdef0123456789abcdef0123456789");
+    }
+
+    #[test]
+    fn ignores_unsupported_interactions() {
+        assert!(route(include_str!("testdata/message_action.json")).is_none());
+    }
+
+    #[test]
+    fn ignores_a_block_action_without_actions() {
+        let mut payload: serde_json::Value = serde_json::from_str(include_str!("testdata/block_actions.json")).unwrap();
+        payload["actions"] = serde_json::json!([]);
+        assert!(route(&payload.to_string()).is_none());
+    }
+
+    #[test]
+    fn falls_back_to_the_name_when_the_username_is_missing() {
+        let mut payload: serde_json::Value = serde_json::from_str(include_str!("testdata/block_actions.json")).unwrap();
+        payload["user"].as_object_mut().unwrap().remove("username");
+        payload["user"]["name"] = serde_json::json!("山田");
+        let message = route(&payload.to_string()).unwrap();
+        assert_eq!(message["command"]["user_name"], "山田");
+    }
+}
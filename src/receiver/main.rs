@@ -1,20 +1,22 @@
-use lambda_http::{run, service_fn, Error, Request, Response, Body};
-use serde::Serialize;
+use lambda_http::{run, service_fn, Error, Request, RequestExt, Response, Body};
 use std::collections::HashMap;
 
 // Import shared types and local modules
 mod types;
 mod slack;
+mod interaction;
+mod workspace;
+mod oauth;
+mod events;
 
-use types::{SlackCommand, SlackResponse};
-
-#[derive(Debug, Serialize)]
-struct SqsMessage {
-    command: SlackCommand,
-    timestamp: String,
-}
+use types::{EventEnvelope, InteractionPayload, SlackCommand, SlackResponse, SqsMessage};
 
 async fn function_handler(event: Request) -> Result<Response<Body>, Error> {
+    // The install flow is a browser visit, not a signed request from Slack
+    if event.method() == lambda_http::http::Method::GET {
+        return handle_oauth(&event).await;
+    }
+
     // Get body as string
     let body_bytes = event.body().to_vec();
     let body_string = String::from_utf8(body_bytes)?;
@@ -28,19 +30,52 @@ async fn function_handler(event: Request) -> Result<Response<Body>, Error> {
         .get("X-Slack-Request-Timestamp")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
-    let signing_secret = std::env::var("SLACK_SIGNING_SECRET")?;
+    // Events API requests are JSON; slash commands and interactions are form encoded
+    let is_json = event.headers()
+        .get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
 
-    // Verify Slack signature
-    if !slack::verify_slack_signature(&signing_secret, &body_string, slack_timestamp, slack_signature)? {
+    // Each workspace can have its own Slack app, so the secret depends on the sender's team
+    let team_id = workspace::team_id(&body_string, is_json);
+    let mut verified = false;
+    for signing_secret in workspace::signing_secrets(team_id.as_deref())? {
+        if slack::verify_slack_signature(&signing_secret, &body_string, slack_timestamp, slack_signature)? {
+            verified = true;
+            break;
+        }
+    }
+    if !verified {
         return Ok(Response::builder()
             .status(401)
             .header("Content-Type", "text/plain")
             .body(Body::from("Unauthorized"))?);
     }
 
+    // Installing the app does not tell us where the team's records go; an administrator has to
+    // add the team to TEAM_CONFIGS (or DEFAULT_TEAM_ID) first
+    if let Some(team_id) = team_id.as_deref() {
+        if !workspace::is_configured(team_id)? {
+            tracing::warn!("Ignoring request from workspace {} that is not configured", team_id);
+            let is_command = !is_json
+                && serde_urlencoded::from_str::<HashMap<String, String>>(&body_string)
+                    .is_ok_and(|params| !params.contains_key("payload"));
+            return not_configured(is_command);
+        }
+    }
+
+    if is_json {
+        return handle_event(&body_string, slack_timestamp).await;
+    }
+
     // Parse form data
     let params: HashMap<String, String> = serde_urlencoded::from_str(&body_string)?;
-    
+
+    // Button clicks, modal submissions and shortcuts arrive as a JSON `payload` form field
+    if let Some(payload) = params.get("payload") {
+        return handle_interaction(payload, slack_timestamp).await;
+    }
+
     let command = SlackCommand {
         token: params.get("token").unwrap_or(&String::new()).clone(),
         team_id: params.get("team_id").unwrap_or(&String::new()).clone(),
@@ -55,18 +90,22 @@ async fn function_handler(event: Request) -> Result<Response<Body>, Error> {
         trigger_id: params.get("trigger_id").unwrap_or(&String::new()).clone(),
     };
 
+    if command.text.split_whitespace().next().is_some_and(|word| word.eq_ignore_ascii_case("fix")) {
+        return handle_fix_command(command, slack_timestamp).await;
+    }
+
     // Send message to SQS
-    let sqs_message = SqsMessage {
+    let sqs_message = SqsMessage::SlashCommand {
         command: command.clone(),
         timestamp: slack_timestamp.to_string(),
     };
 
     send_to_sqs(sqs_message).await?;
 
-    // Return immediate response to Slack
+    // Return immediate response to Slack (only the caller sees the acknowledgement)
     let response = SlackResponse {
-        response_type: "in_channel".to_string(),
-        text: format!("コマンドを受け付けました。処理中です... ⏳"),
+        response_type: "ephemeral".to_string(),
+        text: "コマンドを受け付けました。処理中です... ⏳".to_string(),
     };
 
     Ok(Response::builder()
@@ -75,6 +114,192 @@ async fn function_handler(event: Request) -> Result<Response<Body>, Error> {
         .body(Body::from(serde_json::to_string(&response)?))?)
 }
 
+async fn handle_fix_command(command: SlackCommand, slack_timestamp: &str) -> Result<Response<Body>, Error> {
+    let bot_token = workspace::bot_token(&command.team_id).await?;
+
+    let view_id = match slack::open_loading_modal(&bot_token, &command.trigger_id, "打刻修正").await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to open correction modal: {}", e);
+            let response = SlackResponse {
+                response_type: "ephemeral".to_string(),
+                text: "修正画面を開けませんでした。もう一度お試しください。".to_string(),
+            };
+            return Ok(Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&response)?))?);
+        }
+    };
+
+    send_to_sqs(SqsMessage::FixModal {
+        command,
+        view_id,
+        timestamp: slack_timestamp.to_string(),
+    })
+    .await?;
+
+    // The modal itself is the response, so no message is posted
+    Ok(Response::builder()
+        .status(200)
+        .body(Body::Empty)?)
+}
+
+async fn handle_event(body: &str, slack_timestamp: &str) -> Result<Response<Body>, Error> {
+    match serde_json::from_str::<EventEnvelope>(body)? {
+        EventEnvelope::UrlVerification { challenge } => {
+            return Ok(Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::json!({ "challenge": challenge }).to_string()))?);
+        }
+        // Slack retries events it thinks timed out, even when the first delivery was queued.
+        // Only the first delivery of an event_id is queued; if queueing fails the claim is
+        // dropped so the retry gets its turn
+        EventEnvelope::EventCallback { team_id, event_id, event } => {
+            if !events::claim(&event_id).await? {
+                tracing::info!("Skipping duplicate delivery of event {}", event_id);
+            } else if let Err(e) = send_to_sqs(SqsMessage::Event {
+                team_id,
+                event,
+                timestamp: slack_timestamp.to_string(),
+            })
+            .await
+            {
+                if let Err(release_error) = events::release(&event_id).await {
+                    tracing::error!("Failed to release event {}: {}", event_id, release_error);
+                }
+                return Err(e);
+            }
+        }
+        EventEnvelope::Unsupported => tracing::info!("Ignoring unsupported event envelope"),
+    }
+
+    Ok(Response::builder()
+        .status(200)
+        .body(Body::Empty)?)
+}
+
+async fn handle_interaction(payload: &str, slack_timestamp: &str) -> Result<Response<Body>, Error> {
+    let payload: InteractionPayload = serde_json::from_str(payload)?;
+
+    match interaction::to_sqs_message(payload, slack_timestamp) {
+        Some(message) => send_to_sqs(message).await?,
+        None => tracing::info!("Ignoring unsupported interaction payload"),
+    }
+
+    // An empty 200 acknowledges the interaction (and closes the modal for view submissions)
+    Ok(Response::builder()
+        .status(200)
+        .body(Body::Empty)?)
+}
+
+/// `GET /slack/oauth`: without a code, sends the browser to Slack's consent page; on the
+/// redirect back, exchanges the code and stores the workspace's bot token.
+async fn handle_oauth(event: &Request) -> Result<Response<Body>, Error> {
+    let settings = oauth::OAuthSettings::from_env()?;
+    let params = event.query_string_parameters();
+
+    if params.first("code").is_none() && params.first("error").is_none() {
+        // The nonce goes into both the state and a cookie, so only the browser that
+        // started the install can finish it
+        let nonce = oauth::new_nonce()?;
+        return Ok(Response::builder()
+            .status(302)
+            .header("Location", settings.authorize_url(&nonce)?)
+            .header("Set-Cookie", state_cookie(&nonce, oauth::STATE_TTL))
+            .body(Body::Empty)?);
+    }
+
+    let mut response = finish_install(&settings, event).await?;
+    // The nonce is single use
+    response
+        .headers_mut()
+        .insert("Set-Cookie", state_cookie("", 0).parse()?);
+    Ok(response)
+}
+
+/// The redirect back from Slack's consent page.
+async fn finish_install(settings: &oauth::OAuthSettings, event: &Request) -> Result<Response<Body>, Error> {
+    let params = event.query_string_parameters();
+    if params.first("error").is_some() {
+        return html_page(200, "インストールがキャンセルされました。");
+    }
+
+    let nonce = cookie(event.headers(), oauth::STATE_COOKIE);
+    if !params.first("state").is_some_and(|state| settings.verify_state(state, nonce.as_deref())) {
+        return html_page(400, "インストールのリンクが無効か期限切れです。もう一度インストールしてください。");
+    }
+
+    let Some(code) = params.first("code") else {
+        return html_page(400, "インストールに失敗しました。もう一度お試しください。");
+    };
+    let installation = match settings.exchange_code(code).await {
+        Ok(installation) => installation,
+        Err(e) => {
+            tracing::error!("OAuth code exchange failed: {}", e);
+            return html_page(400, "インストールに失敗しました。もう一度お試しください。");
+        }
+    };
+    oauth::save_installation(&installation).await?;
+    tracing::info!("Installed to workspace {} ({})", installation.team_id, installation.team_name);
+
+    html_page(200, &format!(
+        "{} へのインストールが完了しました。Slackに戻って <code>/attendance</code> をお使いください。",
+        html_escape(&installation.team_name)
+    ))
+}
+
+fn state_cookie(nonce: &str, max_age: i64) -> String {
+    format!(
+        "{}={}; Max-Age={}; Path=/; HttpOnly; Secure; SameSite=Lax",
+        oauth::STATE_COOKIE, nonce, max_age
+    )
+}
+
+/// Value of the named cookie. Pairs without `=` and header values that are not text are skipped.
+fn cookie(headers: &lambda_http::http::HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            (key == name).then(|| value.to_string())
+        })
+}
+
+/// Slash commands show the reply to the user; events and interactions only need the 200.
+fn not_configured(is_command: bool) -> Result<Response<Body>, Error> {
+    if !is_command {
+        return Ok(Response::builder().status(200).body(Body::Empty)?);
+    }
+
+    let response = SlackResponse {
+        response_type: "ephemeral".to_string(),
+        text: "このワークスペースでは勤怠管理の設定がまだ完了していません。管理者に連絡してください。".to_string(),
+    };
+    Ok(Response::builder()
+        .status(200)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&response)?))?)
+}
+
+fn html_page(status: u16, message: &str) -> Result<Response<Body>, Error> {
+    Ok(Response::builder()
+        .status(status)
+        .header("Content-Type", "text/html; charset=utf-8")
+        .body(Body::from(format!(
+            "<!DOCTYPE html><html lang=\"ja\"><head><meta charset=\"utf-8\"><title>勤怠管理</title></head><body><p>{}</p></body></html>",
+            message
+        )))?)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
 async fn send_to_sqs(message: SqsMessage) -> Result<(), Error> {
     let config = aws_config::load_from_env().await;
     let sqs_client = aws_sdk_sqs::Client::new(&config);
@@ -102,4 +327,43 @@ async fn main() -> Result<(), Error> {
         .init();
 
     run(service_fn(function_handler)).await
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::http::{HeaderMap, HeaderValue};
+
+    fn headers(cookies: &[&[u8]]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for cookie in cookies {
+            headers.append("Cookie", HeaderValue::from_bytes(cookie).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn reads_the_state_cookie_among_others() {
+        let headers = headers(&[b"theme=dark; slack_oauth_state=abc123; lang=ja"]);
+        assert_eq!(cookie(&headers, oauth::STATE_COOKIE).as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn reads_cookies_from_every_cookie_header() {
+        let headers = headers(&[b"theme=dark", b"slack_oauth_state=abc123"]);
+        assert_eq!(cookie(&headers, oauth::STATE_COOKIE).as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn missing_state_cookie_is_none() {
+        assert_eq!(cookie(&headers(&[]), oauth::STATE_COOKIE), None);
+        let headers = headers(&[b"slack_oauth_state_old=abc123; xslack_oauth_state=def456"]);
+        assert_eq!(cookie(&headers, oauth::STATE_COOKIE), None);
+    }
+
+    #[test]
+    fn skips_malformed_cookie_headers() {
+        let headers = headers(&[b"slack_oauth_state; ;=x; theme", b"\xff\xfe=1", b"slack_oauth_state=abc123"]);
+        assert_eq!(cookie(&headers, oauth::STATE_COOKIE).as_deref(), Some("abc123"));
+    }
+}
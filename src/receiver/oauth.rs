@@ -0,0 +1,265 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Bot scopes requested when a workspace installs the app
+const BOT_SCOPES: &str = "commands,chat:write,users:read,channels:history,reactions:read,files:write,im:write";
+
+/// How long an install link stays valid (seconds)
+pub const STATE_TTL: i64 = 600;
+
+/// Cookie holding the nonce of the install started in this browser
+pub const STATE_COOKIE: &str = "slack_oauth_state";
+
+/// Client credentials of the distributed Slack app (`SLACK_CLIENT_ID` / `SLACK_CLIENT_SECRET`).
+pub struct OAuthSettings {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl OAuthSettings {
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let client_id = std::env::var("SLACK_CLIENT_ID").unwrap_or_default();
+        let client_secret = std::env::var("SLACK_CLIENT_SECRET").unwrap_or_default();
+        if client_id.is_empty() || client_secret.is_empty() {
+            return Err("SLACK_CLIENT_ID and SLACK_CLIENT_SECRET are required for the OAuth install flow".into());
+        }
+        Ok(Self { client_id, client_secret })
+    }
+
+    /// Slack's consent page. The state carries the nonce that is also set as `STATE_COOKIE`,
+    /// and is signed with the client secret so the redirect can be checked without storing
+    /// anything between the two requests.
+    pub fn authorize_url(&self, nonce: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let state = self.state(nonce, chrono::Utc::now().timestamp())?;
+        let query = serde_urlencoded::to_string([
+            ("client_id", self.client_id.as_str()),
+            ("scope", BOT_SCOPES),
+            ("state", state.as_str()),
+        ])?;
+        Ok(format!("https://slack.com/oauth/v2/authorize?{}", query))
+    }
+
+    /// Checks that the state came from `authorize_url`, has not expired, and belongs to the
+    /// install started in this browser (`nonce` is the value of `STATE_COOKIE`).
+    pub fn verify_state(&self, state: &str, nonce: Option<&str>) -> bool {
+        self.verify_state_at(state, nonce, chrono::Utc::now().timestamp())
+    }
+
+    /// `{issued_at}.{nonce}.{signature}`
+    fn state(&self, nonce: &str, issued_at: i64) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let payload = format!("{}.{}", issued_at, nonce);
+        let signature = hex::encode(self.mac(&payload)?.finalize().into_bytes());
+        Ok(format!("{}.{}", payload, signature))
+    }
+
+    fn verify_state_at(&self, state: &str, nonce: Option<&str>, now: i64) -> bool {
+        let Some((payload, signature)) = state.rsplit_once('.') else {
+            return false;
+        };
+        let Some((timestamp, state_nonce)) = payload.split_once('.') else {
+            return false;
+        };
+        let Ok(issued_at) = timestamp.parse::<i64>() else {
+            return false;
+        };
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        (0..=STATE_TTL).contains(&(now - issued_at))
+            && nonce.is_some_and(|nonce| !nonce.is_empty() && nonce == state_nonce)
+            // verify_slice compares in constant time
+            && self.mac(payload).is_ok_and(|mac| mac.verify_slice(&signature).is_ok())
+    }
+
+    fn mac(&self, payload: &str) -> Result<HmacSha256, Box<dyn std::error::Error + Send + Sync>> {
+        let mut mac = HmacSha256::new_from_slice(self.client_secret.as_bytes())?;
+        mac.update(format!("oauth-state:{}", payload).as_bytes());
+        Ok(mac)
+    }
+
+    /// Exchanges the code from the redirect for the workspace's bot token (oauth.v2.access).
+    pub async fn exchange_code(&self, code: &str) -> Result<Installation, Box<dyn std::error::Error + Send + Sync>> {
+        let response = reqwest::Client::new()
+            .post("https://slack.com/api/oauth.v2.access")
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("code", code),
+            ])
+            .send()
+            .await?;
+
+        let data: OAuthAccessResponse = response.json().await?;
+        if !data.ok {
+            return Err(format!("Slack API error (oauth.v2.access): {}", data.error.unwrap_or_default()).into());
+        }
+
+        let team = data.team.ok_or("oauth.v2.access response has no team")?;
+        Ok(Installation {
+            team_id: team.id,
+            team_name: team.name.unwrap_or_default(),
+            bot_token: data.access_token.ok_or("oauth.v2.access response has no access token")?,
+            bot_user_id: data.bot_user_id.unwrap_or_default(),
+            installed_by: data.authed_user.map(|user| user.id).unwrap_or_default(),
+            installed_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthAccessResponse {
+    ok: bool,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    access_token: Option<String>,
+    #[serde(default)]
+    bot_user_id: Option<String>,
+    #[serde(default)]
+    team: Option<OAuthTeam>,
+    #[serde(default)]
+    authed_user: Option<OAuthUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthTeam {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthUser {
+    id: String,
+}
+
+/// Random value tying an install to the browser that started it.
+pub fn new_nonce() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| format!("getrandom: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+/// A workspace that installed the app through the OAuth flow.
+pub struct Installation {
+    pub team_id: String,
+    pub team_name: String,
+    pub bot_token: String,
+    pub bot_user_id: String,
+    /// Slack user who approved the install
+    pub installed_by: String,
+    pub installed_at: String,
+}
+
+/// DynamoDB table holding one item per installed team (`SLACK_INSTALLATIONS_TABLE`).
+/// The table is encrypted at rest with a customer managed KMS key.
+fn installations_table() -> Option<String> {
+    std::env::var("SLACK_INSTALLATIONS_TABLE").ok().filter(|t| !t.is_empty())
+}
+
+/// Stores the installation, replacing the token of an earlier install by the same team.
+pub async fn save_installation(installation: &Installation) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let table = installations_table().ok_or("SLACK_INSTALLATIONS_TABLE is not set")?;
+    let config = aws_config::load_from_env().await;
+    let client = aws_sdk_dynamodb::Client::new(&config);
+
+    client
+        .put_item()
+        .table_name(table)
+        .item("team_id", AttributeValue::S(installation.team_id.clone()))
+        .item("team_name", AttributeValue::S(installation.team_name.clone()))
+        .item("bot_token", AttributeValue::S(installation.bot_token.clone()))
+        .item("bot_user_id", AttributeValue::S(installation.bot_user_id.clone()))
+        .item("installed_by", AttributeValue::S(installation.installed_by.clone()))
+        .item("installed_at", AttributeValue::S(installation.installed_at.clone()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/// Bot token stored for the team by an earlier install, if any.
+pub async fn installed_bot_token(team_id: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(table) = installations_table() else {
+        return Ok(None);
+    };
+    let config = aws_config::load_from_env().await;
+    let client = aws_sdk_dynamodb::Client::new(&config);
+
+    let output = client
+        .get_item()
+        .table_name(table)
+        .key("team_id", AttributeValue::S(team_id.to_string()))
+        .projection_expression("bot_token")
+        .send()
+        .await?;
+
+    Ok(output
+        .item
+        .and_then(|item| item.get("bot_token")?.as_s().ok().cloned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_790_000_000;
+
+    fn settings() -> OAuthSettings {
+        OAuthSettings {
+            client_id: "123.456".to_string(),
+            client_secret: "client-secret".to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_a_fresh_state_with_its_cookie() {
+        let state = settings().state("abc123", NOW).unwrap();
+        assert!(settings().verify_state_at(&state, Some("abc123"), NOW + 60));
+    }
+
+    #[test]
+    fn rejects_a_forged_signature() {
+        let state = settings().state("abc123", NOW).unwrap();
+        let forged = format!("{}.{}", state.rsplit_once('.').unwrap().0, "00".repeat(32));
+        assert!(!settings().verify_state_at(&forged, Some("abc123"), NOW));
+
+        let other_secret = OAuthSettings {
+            client_secret: "another-secret".to_string(),
+            ..settings()
+        };
+        let state = other_secret.state("abc123", NOW).unwrap();
+        assert!(!settings().verify_state_at(&state, Some("abc123"), NOW));
+
+        let not_hex = format!("{}.{}", NOW, "abc123.not-a-signature");
+        assert!(!settings().verify_state_at(&not_hex, Some("abc123"), NOW));
+    }
+
+    #[test]
+    fn rejects_an_expired_state() {
+        let state = settings().state("abc123", NOW).unwrap();
+        assert!(settings().verify_state_at(&state, Some("abc123"), NOW + STATE_TTL));
+        assert!(!settings().verify_state_at(&state, Some("abc123"), NOW + STATE_TTL + 1));
+        // A state issued in the future is not accepted either
+        assert!(!settings().verify_state_at(&state, Some("abc123"), NOW - 1));
+    }
+
+    #[test]
+    fn rejects_a_missing_or_mismatched_nonce() {
+        let state = settings().state("abc123", NOW).unwrap();
+        assert!(!settings().verify_state_at(&state, None, NOW));
+        assert!(!settings().verify_state_at(&state, Some(""), NOW));
+        assert!(!settings().verify_state_at(&state, Some("def456"), NOW));
+    }
+
+    #[test]
+    fn rejects_a_malformed_state() {
+        for state in ["", "abc123", "1790000000.abc123", "soon.abc123.00"] {
+            assert!(!settings().verify_state_at(state, Some("abc123"), NOW), "{}", state);
+        }
+    }
+}
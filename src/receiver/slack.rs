@@ -29,4 +29,45 @@ pub fn verify_slack_signature(
     let computed_signature = format!("v0={}", hex::encode(mac.finalize().into_bytes()));
     
     Ok(computed_signature == signature)
-}
\ No newline at end of file
+}
+
+/// Opens a placeholder modal with views.open and returns its view id.
+/// This has to happen within Slack's 3-second `trigger_id` window, so the receiver
+/// calls it directly and the processor fills the modal in later with views.update.
+pub async fn open_loading_modal(
+    bot_token: &str,
+    trigger_id: &str,
+    title: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let body = serde_json::json!({
+        "trigger_id": trigger_id,
+        "view": {
+            "type": "modal",
+            "title": { "type": "plain_text", "text": title },
+            "close": { "type": "plain_text", "text": "キャンセル" },
+            "blocks": [
+                {
+                    "type": "section",
+                    "text": { "type": "plain_text", "text": "読み込み中です... ⏳" }
+                }
+            ]
+        }
+    });
+
+    let response = reqwest::Client::new()
+        .post("https://slack.com/api/views.open")
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .json(&body)
+        .send()
+        .await?;
+
+    let data: serde_json::Value = response.json().await?;
+    if !data["ok"].as_bool().unwrap_or(false) {
+        return Err(format!("Slack API error (views.open): {}", data["error"]).into());
+    }
+
+    data["view"]["id"]
+        .as_str()
+        .map(|id| id.to_string())
+        .ok_or_else(|| "views.open response has no view id".into())
+}
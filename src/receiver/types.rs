@@ -19,4 +19,200 @@ pub struct SlackCommand {
 pub struct SlackResponse {
     pub response_type: String,
     pub text: String,
-}
\ No newline at end of file
+}
+/// Message body queued for the processor. `kind` tells the processor which handler to run.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SqsMessage {
+    SlashCommand {
+        command: SlackCommand,
+        timestamp: String,
+    },
+    BlockActions {
+        command: SlackCommand,
+        action_id: String,
+        timestamp: String,
+    },
+    ViewSubmission {
+        user: InteractionUser,
+        team: InteractionTeam,
+        view: View,
+        timestamp: String,
+    },
+    Shortcut {
+        user: InteractionUser,
+        team: InteractionTeam,
+        callback_id: String,
+        trigger_id: String,
+        timestamp: String,
+    },
+    /// `/attendance fix`: the loading modal is already open and the processor fills it in.
+    FixModal {
+        command: SlackCommand,
+        view_id: String,
+        timestamp: String,
+    },
+    /// Events API callback (messages and reactions)
+    Event {
+        team_id: String,
+        event: serde_json::Value,
+        timestamp: String,
+    },
+}
+
+/// JSON bodies sent to the Events API Request URL.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventEnvelope {
+    UrlVerification {
+        challenge: String,
+    },
+    EventCallback {
+        team_id: String,
+        /// Same for every delivery of the event, including Slack's retries
+        event_id: String,
+        event: serde_json::Value,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+/// Interaction payloads sent as the `payload` form field (buttons, modals and shortcuts).
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InteractionPayload {
+    BlockActions(BlockActionsPayload),
+    ViewSubmission(ViewSubmissionPayload),
+    Shortcut(ShortcutPayload),
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockActionsPayload {
+    pub user: InteractionUser,
+    pub team: InteractionTeam,
+    pub channel: Option<InteractionChannel>,
+    pub response_url: Option<String>,
+    pub trigger_id: String,
+    pub actions: Vec<BlockAction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewSubmissionPayload {
+    pub user: InteractionUser,
+    pub team: InteractionTeam,
+    pub view: View,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShortcutPayload {
+    pub user: InteractionUser,
+    pub team: InteractionTeam,
+    pub callback_id: String,
+    pub trigger_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InteractionUser {
+    pub id: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InteractionTeam {
+    pub id: String,
+    #[serde(default)]
+    pub domain: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InteractionChannel {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockAction {
+    pub action_id: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct View {
+    pub id: String,
+    #[serde(default)]
+    pub callback_id: String,
+    #[serde(default)]
+    pub private_metadata: String,
+    #[serde(default)]
+    pub state: serde_json::Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_an_event_callback() {
+        let envelope: EventEnvelope = serde_json::from_str(include_str!("testdata/event_callback.json")).unwrap();
+        let EventEnvelope::EventCallback { team_id, event_id, event } = envelope else {
+            panic!("not an event callback: {:?}", envelope);
+        };
+        assert_eq!(team_id, "T0123ABCD");
+        assert_eq!(event_id, "Ev0123ABCD");
+        assert_eq!(event["type"], "reaction_added");
+        assert_eq!(event["reaction"], "office");
+    }
+
+    #[test]
+    fn reads_a_url_verification() {
+        let envelope: EventEnvelope = serde_json::from_str(include_str!("testdata/url_verification.json")).unwrap();
+        assert!(matches!(
+            envelope,
+            EventEnvelope::UrlVerification { challenge } if challenge == "3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P"
+        ));
+    }
+
+    #[test]
+    fn other_envelopes_are_unsupported() {
+        let envelope: EventEnvelope = serde_json::from_str(r#"{"type":"app_rate_limited","team_id":"T0123ABCD"}"#).unwrap();
+        assert!(matches!(envelope, EventEnvelope::Unsupported));
+    }
+
+    #[test]
+    fn reads_a_block_actions_payload() {
+        let payload: InteractionPayload = serde_json::from_str(include_str!("testdata/block_actions.json")).unwrap();
+        let InteractionPayload::BlockActions(payload) = payload else {
+            panic!("not block_actions: {:?}", payload);
+        };
+        assert_eq!(payload.user.id, "U0123ABCD");
+        assert_eq!(payload.team.domain, "example");
+        assert_eq!(payload.channel.unwrap().name, "attendance");
+        assert_eq!(payload.actions.len(), 1);
+        assert_eq!(payload.actions[0].action_id, "attendance_break");
+        assert_eq!(payload.actions[0].value, "break");
+    }
+
+    #[test]
+    fn reads_a_view_submission_payload() {
+        let payload: InteractionPayload = serde_json::from_str(include_str!("testdata/view_submission.json")).unwrap();
+        let InteractionPayload::ViewSubmission(payload) = payload else {
+            panic!("not view_submission: {:?}", payload);
+        };
+        assert_eq!(payload.view.id, "V0123ABCD");
+        assert_eq!(payload.view.callback_id, "attendance_fix");
+        assert_eq!(payload.view.private_metadata, "2026-10-16");
+    }
+
+    #[test]
+    fn other_interactions_are_unsupported() {
+        let payload: InteractionPayload = serde_json::from_str(include_str!("testdata/message_action.json")).unwrap();
+        assert!(matches!(payload, InteractionPayload::Unsupported));
+    }
+}
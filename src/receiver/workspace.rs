@@ -0,0 +1,95 @@
+use crate::oauth;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The receiver's part of one `TEAM_CONFIGS` entry. The processor reads the Notion settings
+/// from the same JSON, so unknown fields are ignored here.
+#[derive(Debug, Default, Deserialize)]
+struct TeamSecrets {
+    #[serde(default)]
+    signing_secret: Option<String>,
+    #[serde(default)]
+    slack_bot_token: Option<String>,
+}
+
+fn team_configs() -> Result<HashMap<String, TeamSecrets>, Box<dyn std::error::Error + Send + Sync>> {
+    match std::env::var("TEAM_CONFIGS") {
+        Ok(json) if !json.trim().is_empty() => Ok(serde_json::from_str(&json).map_err(|e| format!("TEAM_CONFIGS: {}", e))?),
+        _ => Ok(HashMap::new()),
+    }
+}
+
+/// Team served by the single-workspace variables (`DEFAULT_TEAM_ID`).
+fn default_team_id() -> Option<String> {
+    std::env::var("DEFAULT_TEAM_ID").ok().filter(|id| !id.is_empty())
+}
+
+/// Whether requests from the team can be processed: it has a `TEAM_CONFIGS` entry or is
+/// `DEFAULT_TEAM_ID`. Without `DEFAULT_TEAM_ID`, every team is let through while `TEAM_CONFIGS`
+/// is empty. Among those, a team that only installed the app is not served either, but telling
+/// it apart takes a DynamoDB lookup, so that check is left to the processor, which looks the
+/// installation up anyway. Same rule as the processor's `Workspaces::for_team`.
+pub fn is_configured(team_id: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let teams = team_configs()?;
+    if teams.contains_key(team_id) {
+        return Ok(true);
+    }
+
+    match default_team_id() {
+        Some(default_team_id) => Ok(default_team_id == team_id),
+        None => Ok(teams.is_empty()),
+    }
+}
+
+/// Reads the sender's team ID from the raw body so the right signing secret can be chosen
+/// before the signature is checked. Nothing else in the body is trusted at this point.
+pub fn team_id(body: &str, is_json: bool) -> Option<String> {
+    if is_json {
+        let value: serde_json::Value = serde_json::from_str(body).ok()?;
+        return value["team_id"].as_str().map(str::to_string);
+    }
+
+    let params: HashMap<String, String> = serde_urlencoded::from_str(body).ok()?;
+    if let Some(team_id) = params.get("team_id") {
+        return Some(team_id.clone());
+    }
+    let payload: serde_json::Value = serde_json::from_str(params.get("payload")?).ok()?;
+    payload["team"]["id"].as_str().map(str::to_string)
+}
+
+/// Signing secrets a request may be signed with: the team's own secret when it has one,
+/// otherwise `SLACK_SIGNING_SECRET`. Bodies without a team ID (`url_verification`) are
+/// also checked against every team's secret so each app can verify its Request URL.
+pub fn signing_secrets(team_id: Option<&str>) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let teams = team_configs()?;
+    if let Some(secret) = team_id.and_then(|id| teams.get(id)?.signing_secret.clone()) {
+        return Ok(vec![secret]);
+    }
+
+    let mut secrets: Vec<String> = std::env::var("SLACK_SIGNING_SECRET")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .into_iter()
+        .collect();
+    if team_id.is_none() {
+        secrets.extend(teams.into_values().filter_map(|team| team.signing_secret));
+    }
+
+    if secrets.is_empty() {
+        return Err(format!("No signing secret configured for team {:?}", team_id).into());
+    }
+    Ok(secrets)
+}
+
+/// Bot token for calling Slack on behalf of the team: its `TEAM_CONFIGS` entry, then the
+/// token stored when the team installed the app, then `SLACK_BOT_TOKEN`.
+pub async fn bot_token(team_id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(token) = team_configs()?.remove(team_id).and_then(|team| team.slack_bot_token) {
+        return Ok(token);
+    }
+    if let Some(token) = oauth::installed_bot_token(team_id).await? {
+        return Ok(token);
+    }
+
+    Ok(std::env::var("SLACK_BOT_TOKEN")?)
+}
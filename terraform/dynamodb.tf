@@ -0,0 +1,151 @@
+# KMS key for the bot tokens stored by the OAuth install flow
+resource "aws_kms_key" "installations_key" {
+  description         = "Encrypts Slack bot tokens of installed workspaces"
+  enable_key_rotation = true
+
+  tags = {
+    Environment = var.environment
+    Purpose     = "SlackInstallations"
+  }
+}
+
+resource "aws_kms_alias" "installations_key_alias" {
+  name          = "alias/${var.lambda_function_name}-installations"
+  target_key_id = aws_kms_key.installations_key.key_id
+}
+
+# Bot token per Slack team, written by the receiver when a workspace installs the app
+resource "aws_dynamodb_table" "slack_installations" {
+  name         = "${var.lambda_function_name}-installations"
+  billing_mode = "PAY_PER_REQUEST"
+  hash_key     = "team_id"
+
+  attribute {
+    name = "team_id"
+    type = "S"
+  }
+
+  server_side_encryption {
+    enabled     = true
+    kms_key_arn = aws_kms_key.installations_key.arn
+  }
+
+  point_in_time_recovery {
+    enabled = true
+  }
+
+  tags = {
+    Environment = var.environment
+    Purpose     = "SlackInstallations"
+  }
+}
+
+# One item per Events API delivery the receiver queued, so Slack's retries are not queued twice
+resource "aws_dynamodb_table" "slack_events" {
+  name         = "${var.lambda_function_name}-events"
+  billing_mode = "PAY_PER_REQUEST"
+  hash_key     = "event_id"
+
+  attribute {
+    name = "event_id"
+    type = "S"
+  }
+
+  ttl {
+    attribute_name = "expires_at"
+    enabled        = true
+  }
+
+  tags = {
+    Environment = var.environment
+    Purpose     = "SlackEventDeduplication"
+  }
+}
+
+# Receiver claims each event_id before queueing it and releases it if queueing fails
+resource "aws_iam_role_policy" "receiver_lambda_events_policy" {
+  name = "${var.lambda_function_name}-receiver-events-policy"
+  role = aws_iam_role.receiver_lambda_role.id
+
+  policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Effect = "Allow"
+        Action = [
+          "dynamodb:PutItem",
+          "dynamodb:DeleteItem"
+        ]
+        Resource = aws_dynamodb_table.slack_events.arn
+      }
+    ]
+  })
+}
+
+# Receiver stores tokens on install and reads them to open modals
+resource "aws_iam_role_policy" "receiver_lambda_installations_policy" {
+  name = "${var.lambda_function_name}-receiver-installations-policy"
+  role = aws_iam_role.receiver_lambda_role.id
+
+  policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Effect = "Allow"
+        Action = [
+          "dynamodb:GetItem",
+          "dynamodb:PutItem"
+        ]
+        Resource = aws_dynamodb_table.slack_installations.arn
+      },
+      {
+        Effect = "Allow"
+        Action = [
+          "kms:Encrypt",
+          "kms:Decrypt",
+          "kms:GenerateDataKey",
+          "kms:DescribeKey"
+        ]
+        Resource = aws_kms_key.installations_key.arn
+        Condition = {
+          StringEquals = {
+            "kms:ViaService" = "dynamodb.${var.aws_region}.amazonaws.com"
+          }
+        }
+      }
+    ]
+  })
+}
+
+# Processor and reminder (shared role) read tokens of installed teams
+resource "aws_iam_role_policy" "processor_lambda_installations_policy" {
+  name = "${var.lambda_function_name}-processor-installations-policy"
+  role = aws_iam_role.lambda_role.id
+
+  policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Effect = "Allow"
+        Action = [
+          "dynamodb:GetItem",
+          "dynamodb:Scan"
+        ]
+        Resource = aws_dynamodb_table.slack_installations.arn
+      },
+      {
+        Effect = "Allow"
+        Action = [
+          "kms:Decrypt",
+          "kms:DescribeKey"
+        ]
+        Resource = aws_kms_key.installations_key.arn
+        Condition = {
+          StringEquals = {
+            "kms:ViaService" = "dynamodb.${var.aws_region}.amazonaws.com"
+          }
+        }
+      }
+    ]
+  })
+}
@@ -70,6 +70,13 @@ resource "aws_lambda_function" "slack_attendance_receiver" {
   environment {
     variables = {
       SLACK_SIGNING_SECRET = var.slack_signing_secret
+      SLACK_BOT_TOKEN      = var.slack_bot_token
+      TEAM_CONFIGS         = var.team_configs
+      DEFAULT_TEAM_ID      = var.default_team_id
+      SLACK_CLIENT_ID      = var.slack_client_id
+      SLACK_CLIENT_SECRET  = var.slack_client_secret
+      SLACK_INSTALLATIONS_TABLE = aws_dynamodb_table.slack_installations.name
+      SLACK_EVENTS_TABLE   = aws_dynamodb_table.slack_events.name
       SQS_QUEUE_URL       = aws_sqs_queue.slack_attendance_queue.url
     }
   }
@@ -77,6 +84,8 @@ resource "aws_lambda_function" "slack_attendance_receiver" {
   depends_on = [
     aws_iam_role_policy_attachment.receiver_lambda_basic_execution,
     aws_iam_role_policy.receiver_lambda_sqs_policy,
+    aws_iam_role_policy.receiver_lambda_installations_policy,
+    aws_iam_role_policy.receiver_lambda_events_policy,
     aws_cloudwatch_log_group.receiver_lambda_logs,
   ]
 }
@@ -91,6 +100,16 @@ resource "aws_api_gateway_integration" "receiver_lambda_integration" {
   uri                     = aws_lambda_function.slack_attendance_receiver.invoke_arn
 }
 
+# OAuth install flow (GET /slack/oauth) is handled by the receiver as well
+resource "aws_api_gateway_integration" "receiver_oauth_integration" {
+  rest_api_id             = aws_api_gateway_rest_api.slack_api.id
+  resource_id             = aws_api_gateway_resource.oauth_resource.id
+  http_method             = aws_api_gateway_method.oauth_get.http_method
+  integration_http_method = "POST"
+  type                    = "AWS_PROXY"
+  uri                     = aws_lambda_function.slack_attendance_receiver.invoke_arn
+}
+
 # API Gateway Integration Responses
 resource "aws_api_gateway_integration_response" "slack_integration_response_200" {
   rest_api_id = aws_api_gateway_rest_api.slack_api.id
@@ -0,0 +1,64 @@
+# Package reminder Lambda function (built from the processor crate)
+data "archive_file" "reminder_lambda_zip" {
+  type        = "zip"
+  source_file = "../target/lambda/slack-attendance-reminder/bootstrap"
+  output_path = "${path.module}/reminder_lambda_deployment.zip"
+}
+
+# CloudWatch Logs for reminder Lambda
+resource "aws_cloudwatch_log_group" "reminder_lambda_logs" {
+  name              = "/aws/lambda/${var.lambda_function_name}-reminder"
+  retention_in_days = var.log_retention_days
+}
+
+# Reminder Lambda function (shares the processor's execution role)
+resource "aws_lambda_function" "slack_attendance_reminder" {
+  filename         = data.archive_file.reminder_lambda_zip.output_path
+  function_name    = "${var.lambda_function_name}-reminder"
+  role            = aws_iam_role.lambda_role.arn
+  handler         = "bootstrap"
+  source_code_hash = data.archive_file.reminder_lambda_zip.output_base64sha256
+  runtime         = "provided.al2023"
+  architectures   = ["x86_64"]
+  memory_size     = var.lambda_memory_size
+  timeout         = var.lambda_timeout
+
+  environment {
+    variables = {
+      NOTION_API_KEY     = var.notion_api_key
+      NOTION_DATABASE_ID = var.notion_database_id
+      SLACK_BOT_TOKEN    = var.slack_bot_token
+      TEAM_CONFIGS       = var.team_configs
+      DEFAULT_TEAM_ID    = var.default_team_id
+      SLACK_INSTALLATIONS_TABLE = aws_dynamodb_table.slack_installations.name
+      REMINDER_HOUR      = var.reminder_hour
+    }
+  }
+
+  depends_on = [
+    aws_iam_role_policy_attachment.lambda_basic_execution,
+    aws_iam_role_policy.processor_lambda_installations_policy,
+    aws_cloudwatch_log_group.reminder_lambda_logs,
+  ]
+}
+
+# EventBridge schedule for missing clock-out reminders
+resource "aws_cloudwatch_event_rule" "reminder_schedule" {
+  name                = "${var.lambda_function_name}-reminder-schedule"
+  description         = "Remind users who have not clocked out"
+  schedule_expression = var.reminder_schedule
+}
+
+resource "aws_cloudwatch_event_target" "reminder_lambda_target" {
+  rule = aws_cloudwatch_event_rule.reminder_schedule.name
+  arn  = aws_lambda_function.slack_attendance_reminder.arn
+}
+
+# Lambda permission for EventBridge to invoke reminder
+resource "aws_lambda_permission" "reminder_eventbridge_invoke" {
+  statement_id  = "AllowEventBridgeInvokeReminder"
+  action        = "lambda:InvokeFunction"
+  function_name = aws_lambda_function.slack_attendance_reminder.function_name
+  principal     = "events.amazonaws.com"
+  source_arn    = aws_cloudwatch_event_rule.reminder_schedule.arn
+}
@@ -54,11 +54,27 @@ resource "aws_lambda_function" "slack_attendance" {
     variables = {
       NOTION_API_KEY     = var.notion_api_key
       NOTION_DATABASE_ID = var.notion_database_id
+      NOTION_AUDIT_DATABASE_ID = var.notion_audit_database_id
+      SLACK_BOT_TOKEN    = var.slack_bot_token
+      TEAM_CONFIGS       = var.team_configs
+      DEFAULT_TEAM_ID    = var.default_team_id
+      SLACK_INSTALLATIONS_TABLE = aws_dynamodb_table.slack_installations.name
+      PUBLIC_PUNCH_CHANNELS = join(",", var.public_punch_channels)
+      EVENT_RULES           = var.event_rules
+      ADMIN_USER_IDS        = join(",", var.admin_user_ids)
+      CORRECTION_APPROVER_IDS = join(",", var.correction_approver_ids)
+      TEAM_REPORT_DELIVERY  = var.team_report_delivery
+      BREAK_DEDUCTION_RULES = var.break_deduction_rules
+      ROUNDING_POLICY       = var.rounding_policy
+      LABOR_RULES           = var.labor_rules
+      COMPANY_HOLIDAYS      = join(",", var.company_holidays)
+      COMPANY_WORKDAYS      = join(",", var.company_workdays)
     }
   }
 
   depends_on = [
     aws_iam_role_policy_attachment.lambda_basic_execution,
+    aws_iam_role_policy.processor_lambda_installations_policy,
     aws_cloudwatch_log_group.lambda_logs,
   ]
 }
@@ -154,6 +170,20 @@ resource "aws_api_gateway_method_response" "slack_response_500" {
   }
 }
 
+# OAuth redirect endpoint for installing the app into a workspace
+resource "aws_api_gateway_resource" "oauth_resource" {
+  rest_api_id = aws_api_gateway_rest_api.slack_api.id
+  parent_id   = aws_api_gateway_resource.slack_resource.id
+  path_part   = "oauth"
+}
+
+resource "aws_api_gateway_method" "oauth_get" {
+  rest_api_id   = aws_api_gateway_rest_api.slack_api.id
+  resource_id   = aws_api_gateway_resource.oauth_resource.id
+  http_method   = "GET"
+  authorization = "NONE"
+}
+
 # Note: API Gateway Integration and Lambda permission are now in lambda_receiver.tf
 # as they point to the receiver Lambda function
 
@@ -163,6 +193,7 @@ resource "aws_api_gateway_deployment" "slack_deployment" {
 
   depends_on = [
     aws_api_gateway_integration.receiver_lambda_integration,
+    aws_api_gateway_integration.receiver_oauth_integration,
     aws_api_gateway_method_response.slack_response_200,
     aws_api_gateway_method_response.slack_response_400,
     aws_api_gateway_method_response.slack_response_500,
@@ -176,6 +207,8 @@ resource "aws_api_gateway_deployment" "slack_deployment" {
     redeployment = sha1(jsonencode([
       aws_api_gateway_method.slack_post.id,
       aws_api_gateway_integration.receiver_lambda_integration.id,
+      aws_api_gateway_method.oauth_get.id,
+      aws_api_gateway_integration.receiver_oauth_integration.id,
       aws_api_gateway_method_response.slack_response_200.id,
       aws_api_gateway_method_response.slack_response_400.id,
       aws_api_gateway_method_response.slack_response_500.id,
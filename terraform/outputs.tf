@@ -13,6 +13,11 @@ output "api_gateway_url" {
   value       = "https://${aws_api_gateway_rest_api.slack_api.id}.execute-api.${var.aws_region}.amazonaws.com/${aws_api_gateway_stage.slack_stage.stage_name}/slack"
 }
 
+output "slack_install_url" {
+  description = "Install link and OAuth Redirect URL for distributing the Slack app"
+  value       = "https://${aws_api_gateway_rest_api.slack_api.id}.execute-api.${var.aws_region}.amazonaws.com/${aws_api_gateway_stage.slack_stage.stage_name}/slack/oauth"
+}
+
 output "api_gateway_id" {
   description = "ID of the API Gateway"
   value       = aws_api_gateway_rest_api.slack_api.id
@@ -23,6 +28,11 @@ output "cloudwatch_log_group" {
   value       = aws_cloudwatch_log_group.lambda_logs.name
 }
 
+output "slack_installations_table" {
+  description = "DynamoDB table holding bot tokens of installed workspaces"
+  value       = aws_dynamodb_table.slack_installations.name
+}
+
 output "sqs_queue_url" {
   description = "URL of the SQS queue"
   value       = aws_sqs_queue.slack_attendance_queue.url
@@ -46,4 +56,9 @@ output "receiver_lambda_function_name" {
 output "receiver_lambda_function_arn" {
   description = "ARN of the receiver Lambda function"
   value       = aws_lambda_function.slack_attendance_receiver.arn
-}
\ No newline at end of file
+}
+
+output "reminder_lambda_function_name" {
+  description = "Name of the reminder Lambda function"
+  value       = aws_lambda_function.slack_attendance_reminder.function_name
+}
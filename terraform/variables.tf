@@ -34,6 +34,12 @@ variable "slack_signing_secret" {
   sensitive   = true
 }
 
+variable "slack_bot_token" {
+  description = "Slack bot token (xoxb-) for Web API calls such as chat.postMessage"
+  type        = string
+  sensitive   = true
+}
+
 variable "notion_api_key" {
   description = "Notion API key"
   type        = string
@@ -46,6 +52,111 @@ variable "notion_database_id" {
   sensitive   = true
 }
 
+variable "team_configs" {
+  description = "JSON object keyed by Slack team ID with per-workspace signing_secret, slack_bot_token, notion_api_key, notion_database_id and notion_audit_database_id. Omitted fields fall back to the single-workspace variables"
+  type        = string
+  default     = ""
+  sensitive   = true
+}
+
+variable "default_team_id" {
+  description = "Slack team ID served by the single-workspace variables. Needed when team_configs or the OAuth install flow is used; other teams must be listed in team_configs"
+  type        = string
+  default     = ""
+}
+
+variable "slack_client_id" {
+  description = "Client ID of the Slack app, for the OAuth install flow (optional; needed only when distributing the app)"
+  type        = string
+  default     = ""
+}
+
+variable "slack_client_secret" {
+  description = "Client secret of the Slack app, for the OAuth install flow"
+  type        = string
+  default     = ""
+  sensitive   = true
+}
+
+variable "notion_audit_database_id" {
+  description = "Notion database ID for the audit log (optional; entries are always written to CloudWatch Logs)"
+  type        = string
+  default     = ""
+  sensitive   = true
+}
+
+variable "public_punch_channels" {
+  description = "Slack channel IDs where punch results are posted to everyone (in_channel). Other responses are ephemeral"
+  type        = list(string)
+  default     = []
+}
+
+variable "event_rules" {
+  description = "JSON array of rules mapping channel messages (regex) or reactions to attendance actions"
+  type        = string
+  default     = "[]"
+}
+
+variable "admin_user_ids" {
+  description = "Slack user IDs allowed to run admin commands (e.g. team reports)"
+  type        = list(string)
+  default     = []
+}
+
+variable "correction_approver_ids" {
+  description = "Slack user IDs who approve punch corrections (defaults to admin_user_ids when empty)"
+  type        = list(string)
+  default     = []
+}
+
+variable "team_report_delivery" {
+  description = "How team reports are delivered: ephemeral or dm"
+  type        = string
+  default     = "ephemeral"
+}
+
+variable "break_deduction_rules" {
+  description = "Automatic break deduction as comma-separated worked_minutes:break_minutes pairs (e.g. 360:45,480:60). Empty disables it"
+  type        = string
+  default     = ""
+}
+
+variable "rounding_policy" {
+  description = "JSON rounding policy for work time, e.g. {\"unit\":15,\"scope\":\"day\",\"in\":\"up\",\"out\":\"down\"}. Empty disables rounding"
+  type        = string
+  default     = ""
+}
+
+variable "labor_rules" {
+  description = "JSON overrides for the statutory rules used in overtime checks, e.g. {\"legal_holiday\":\"sat\",\"monthly_overtime_limit_minutes\":2700}. Empty uses the Labour Standards Act defaults"
+  type        = string
+  default     = ""
+}
+
+variable "company_holidays" {
+  description = "Company-specific days off (YYYY-MM-DD) in addition to weekends and national holidays"
+  type        = list(string)
+  default     = []
+}
+
+variable "company_workdays" {
+  description = "Weekends or national holidays (YYYY-MM-DD) that are scheduled working days"
+  type        = list(string)
+  default     = []
+}
+
+variable "reminder_schedule" {
+  description = "EventBridge schedule expression (UTC) for missing clock-out reminders"
+  type        = string
+  default     = "cron(0 11 ? * MON-FRI *)" # 20:00 JST on weekdays
+}
+
+variable "reminder_hour" {
+  description = "JST hour from which users still working are reminded to clock out"
+  type        = number
+  default     = 20
+}
+
 variable "api_gateway_stage_name" {
   description = "API Gateway stage name"
   type        = string